@@ -0,0 +1,59 @@
+//! Compares the quotient table of `HighestAverages::allocate` with the heap
+//! used by `try_allocate_seats`. Run with `cargo bench`.
+
+extern crate voting;
+
+use std::time::{Duration, Instant};
+
+use voting::highest_averages::{HighestAverages, Method};
+use voting::AllocateSeats;
+
+/// Generate votes with a simple linear congruential generator, so that the
+/// runs are reproducible.
+fn votes(nb_parties: usize, seed: u64) -> Vec<usize> {
+	let mut state = seed;
+	(0..nb_parties)
+		.map(|_| {
+			state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+			(state >> 40) as usize % 1000000 + 1
+		})
+		.collect()
+}
+
+/// Run the function a few times and return the fastest run.
+fn time<F: FnMut() -> Vec<usize>>(mut f: F) -> (Duration, Vec<usize>) {
+	let mut best = None;
+	let mut result = Vec::new();
+	for _ in 0..5 {
+		let start = Instant::now();
+		result = f();
+		let elapsed = start.elapsed();
+		if best.is_none_or(|b| elapsed < b) {
+			best = Some(elapsed);
+		}
+	}
+	(best.unwrap(), result)
+}
+
+fn main() {
+	let cases = [(10, 100), (10, 10000), (500, 5000), (5000, 500), (2000, 20000)];
+	let methods = [Method::DHondt, Method::SainteLague, Method::HuntingtonHill];
+	println!("{:<16} {:>8} {:>8} {:>12} {:>12}", "method", "parties", "seats", "table", "heap");
+	for method in methods.iter() {
+		let allocator = HighestAverages::new(*method);
+		for &(nb_parties, nb_seats) in cases.iter() {
+			let parties = votes(nb_parties, nb_seats as u64);
+			let (table, expected) = time(|| allocator.allocate(nb_seats, &parties).unwrap().seats);
+			let (heap, seats) = time(|| allocator.try_allocate_seats(nb_seats, &parties).unwrap());
+			assert_eq!(expected, seats);
+			println!(
+				"{:<16} {:>8} {:>8} {:>12.3?} {:>12.3?}",
+				format!("{:?}", method),
+				nb_parties,
+				nb_seats,
+				table,
+				heap
+			);
+		}
+	}
+}
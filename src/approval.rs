@@ -0,0 +1,304 @@
+use std::error::Error;
+use std::fmt;
+
+use num_rational::{BigRational, Rational};
+use num_traits::Zero;
+
+use super::{big, to_big};
+use ballot::ApprovalProfile;
+
+/// The weights of the Thiele methods: a voter values the k-th approved member
+/// of the committee by the k-th weight.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Weights {
+	/// 1, 1, 1, ..., electing the candidates with the most approvals.
+	Approval,
+	/// 1, 1/2, 1/3, ..., proportional approval voting (PAV).
+	Proportional,
+	/// 1, 0, 0, ..., a voter only values their first approved member.
+	ChamberlinCourant,
+	/// The given weights, zero for members beyond the end.
+	Custom(Vec<Rational>),
+}
+
+impl Weights {
+	/// The weight of the k-th approved member, starting at 1.
+	pub fn weight(&self, k: usize) -> BigRational {
+		match *self {
+			Weights::Approval => big(1),
+			Weights::Proportional => big(k).recip(),
+			Weights::ChamberlinCourant => big((k == 1) as usize),
+			Weights::Custom(ref weights) => {
+				weights.get(k - 1).map_or_else(BigRational::zero, |w| to_big(*w))
+			}
+		}
+	}
+
+	/// Whether the weights never increase, so that the value of adding a
+	/// candidate can only decrease as the committee grows.
+	fn is_decreasing(&self) -> bool {
+		match *self {
+			Weights::Custom(ref weights) => {
+				let zero = Rational::from_integer(0);
+				weights.windows(2).all(|w| w[0] >= w[1])
+					&& weights.last().is_none_or(|w| *w >= zero)
+			}
+			_ => true,
+		}
+	}
+}
+
+/// The reasons electing a committee can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ApprovalError {
+	/// There are fewer candidates than seats.
+	NotEnoughCandidates,
+	/// There are too many committees, or subsets of the committee, to try.
+	TooLarge,
+}
+
+impl fmt::Display for ApprovalError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ApprovalError::NotEnoughCandidates => write!(f, "fewer candidates than seats"),
+			ApprovalError::TooLarge => write!(f, "too many committees to search"),
+		}
+	}
+}
+
+impl Error for ApprovalError {}
+
+/// An elected committee.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Committee {
+	/// The elected candidates in the order they were elected.
+	pub winners: Vec<usize>,
+	/// The Thiele score of the committee.
+	pub score: BigRational,
+}
+
+/// Implements the Thiele methods, electing the committee that maximises the
+/// total value to the voters.
+/// For more info: https://en.wikipedia.org/wiki/Thiele%27s_voting_rules
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Thiele {
+	weights: Weights,
+}
+
+impl Thiele {
+	pub fn new(weights: Weights) -> Thiele {
+		Thiele {
+			weights,
+		}
+	}
+
+	/// The score of the committee.
+	pub fn score(&self, profile: &ApprovalProfile, committee: &[usize]) -> BigRational {
+		let mut counts = vec![0; profile.ballots().len()];
+		let mut score = BigRational::zero();
+		for c in committee.iter() {
+			score += self.gain(profile, &counts, *c);
+			add_member(profile, &mut counts, *c);
+		}
+		score
+	}
+
+	/// Elect the committee one candidate at a time, each time adding the
+	/// candidate that increases the score most. Ties are broken in favour of
+	/// the lowest candidate index.
+	pub fn sequential(
+		&self,
+		nb_seats: usize,
+		profile: &ApprovalProfile,
+	) -> Result<Committee, ApprovalError> {
+		if nb_seats > profile.nb_candidates() {
+			return Err(ApprovalError::NotEnoughCandidates);
+		}
+		let mut counts = vec![0; profile.ballots().len()];
+		let mut elected = vec![false; profile.nb_candidates()];
+		let mut committee = Committee {
+			winners: Vec::with_capacity(nb_seats),
+			score: BigRational::zero(),
+		};
+		for _ in 0..nb_seats {
+			let mut best: Option<(usize, BigRational)> = None;
+			for c in (0..profile.nb_candidates()).filter(|c| !elected[*c]) {
+				let gain = self.gain(profile, &counts, c);
+				if best.as_ref().is_none_or(|(_, g)| gain > *g) {
+					best = Some((c, gain));
+				}
+			}
+			let (c, gain) = best.unwrap();
+			elected[c] = true;
+			add_member(profile, &mut counts, c);
+			committee.winners.push(c);
+			committee.score += gain;
+		}
+		Ok(committee)
+	}
+
+	/// Elect the committee with the highest score by branch and bound. Ties
+	/// are broken in favour of the committee with the lowest candidate indices.
+	/// The number of committees grows quickly, so this is only feasible for
+	/// small elections.
+	pub fn exact(
+		&self,
+		nb_seats: usize,
+		profile: &ApprovalProfile,
+	) -> Result<Committee, ApprovalError> {
+		if nb_seats > profile.nb_candidates() {
+			return Err(ApprovalError::NotEnoughCandidates);
+		}
+		let mut search = Search {
+			thiele: self,
+			profile,
+			nb_seats,
+			bound: self.weights.is_decreasing(),
+			counts: vec![0; profile.ballots().len()],
+			committee: Vec::with_capacity(nb_seats),
+			best: None,
+		};
+		search.search(0, BigRational::zero());
+		Ok(search.best.unwrap())
+	}
+
+	/// The increase of the score when adding the candidate to a committee, given
+	/// the number of members each ballot approves.
+	fn gain(&self, profile: &ApprovalProfile, counts: &[usize], candidate: usize) -> BigRational {
+		let mut gain = BigRational::zero();
+		for (ballot, count) in profile.ballots().iter().zip(counts.iter()) {
+			if ballot.approves(candidate) {
+				gain += big(ballot.weight) * self.weights.weight(count + 1);
+			}
+		}
+		gain
+	}
+}
+
+/// The state of the branch and bound search for the best committee.
+struct Search<'a> {
+	thiele: &'a Thiele,
+	profile: &'a ApprovalProfile,
+	nb_seats: usize,
+	bound: bool,
+	counts: Vec<usize>,
+	committee: Vec<usize>,
+	best: Option<Committee>,
+}
+
+impl<'a> Search<'a> {
+	/// Try all committees extending the current committee with candidates from
+	/// the given index onwards.
+	fn search(&mut self, start: usize, score: BigRational) {
+		let needed = self.nb_seats - self.committee.len();
+		if needed == 0 {
+			if self.best.as_ref().is_none_or(|b| score > b.score) {
+				self.best = Some(Committee {
+					winners: self.committee.clone(),
+					score,
+				});
+			}
+			return;
+		}
+		let nb_candidates = self.profile.nb_candidates();
+		let mut gains = Vec::with_capacity(nb_candidates - start);
+		for c in start..nb_candidates {
+			gains.push(self.thiele.gain(self.profile, &self.counts, c));
+		}
+		if let (true, Some(best)) = (self.bound, self.best.as_ref()) {
+			// Adding candidates can't gain more than their current gains.
+			let mut sorted = gains.clone();
+			sorted.sort_by(|a, b| b.cmp(a));
+			let bound = sorted.into_iter().take(needed).fold(score.clone(), |a, b| a + b);
+			if bound <= best.score {
+				return;
+			}
+		}
+		for c in start..(nb_candidates + 1 - needed) {
+			self.committee.push(c);
+			add_member(self.profile, &mut self.counts, c);
+			self.search(c + 1, &score + &gains[c - start]);
+			remove_member(self.profile, &mut self.counts, c);
+			self.committee.pop();
+		}
+	}
+}
+
+fn add_member(profile: &ApprovalProfile, counts: &mut [usize], candidate: usize) {
+	for (ballot, count) in profile.ballots().iter().zip(counts.iter_mut()) {
+		if ballot.approves(candidate) {
+			*count += 1;
+		}
+	}
+}
+
+fn remove_member(profile: &ApprovalProfile, counts: &mut [usize], candidate: usize) {
+	for (ballot, count) in profile.ballots().iter().zip(counts.iter_mut()) {
+		if ballot.approves(candidate) {
+			*count -= 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::approval_profile;
+
+	#[test]
+	fn weights() {
+		assert_eq!(big(1) / big(3), Weights::Proportional.weight(3));
+		assert_eq!(big(0), Weights::ChamberlinCourant.weight(2));
+		let custom = Weights::Custom(vec![Rational::from_integer(2), Rational::from_integer(1)]);
+		assert_eq!(big(0), custom.weight(3));
+		assert!(custom.is_decreasing());
+		assert!(!Weights::Custom(vec![Rational::from_integer(1), Rational::from_integer(2)])
+			.is_decreasing());
+	}
+
+	#[test]
+	fn approval_and_pav() {
+		let profile = approval_profile(3, vec![(6, vec![0, 1]), (4, vec![2])]);
+		let av = Thiele::new(Weights::Approval);
+		assert_eq!(vec![0, 1], av.sequential(2, &profile).unwrap().winners);
+		assert_eq!(vec![0, 1], av.exact(2, &profile).unwrap().winners);
+		let pav = Thiele::new(Weights::Proportional);
+		let committee = pav.sequential(2, &profile).unwrap();
+		assert_eq!(vec![0, 2], committee.winners);
+		assert_eq!(big(10), committee.score);
+		assert_eq!(big(9), pav.score(&profile, &[0, 1]));
+		assert_eq!(Err(ApprovalError::NotEnoughCandidates), pav.exact(4, &profile));
+	}
+
+	#[test]
+	fn exact_pav() {
+		let profile =
+			approval_profile(4, vec![(3, vec![3]), (5, vec![0, 1]), (5, vec![0]), (6, vec![1, 3])]);
+		let pav = Thiele::new(Weights::Proportional);
+		let sequential = pav.sequential(2, &profile).unwrap();
+		assert_eq!(vec![1, 0], sequential.winners);
+		assert_eq!(big(37) / big(2), sequential.score);
+		let exact = pav.exact(2, &profile).unwrap();
+		assert_eq!(vec![0, 3], exact.winners);
+		assert_eq!(big(19), exact.score);
+
+		// Increasing weights can't be bounded, but are still searched.
+		let custom = Thiele::new(Weights::Custom(vec![
+			Rational::from_integer(1),
+			Rational::from_integer(3),
+		]));
+		assert_eq!(vec![1, 3], custom.exact(2, &profile).unwrap().winners);
+		let cc = Thiele::new(Weights::ChamberlinCourant);
+		assert_eq!(vec![0, 3], cc.exact(2, &profile).unwrap().winners);
+	}
+
+	#[test]
+	fn large_committee() {
+		// The harmonic numbers quickly outgrow fixed-width rationals.
+		let profile = approval_profile(60, vec![(1, (0..60).collect())]);
+		let committee = Thiele::new(Weights::Proportional).sequential(50, &profile).unwrap();
+		assert_eq!((0..50).collect::<Vec<_>>(), committee.winners);
+		let harmonic = (1..51).fold(big(0), |sum, k| sum + big(k).recip());
+		assert_eq!(harmonic, committee.score);
+	}
+}
@@ -0,0 +1,466 @@
+use std::error::Error;
+use std::fmt;
+
+/// A ranked ballot. Candidates are ranked from most to least preferred, where
+/// every rank can hold several candidates that are ranked equally, or none if
+/// the voter skipped a ranking. Candidates that are not ranked are preferred
+/// less than all ranked candidates. A candidate ranked more than once counts
+/// at their highest rank.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ballot {
+	/// The number of voters that cast this ballot.
+	pub weight: usize,
+	/// The ranks from most to least preferred.
+	pub ranks: Vec<Vec<usize>>,
+}
+
+impl Ballot {
+	/// Create a ballot with a strict order of preference.
+	pub fn new(weight: usize, preferences: Vec<usize>) -> Ballot {
+		Ballot {
+			weight,
+			ranks: preferences.into_iter().map(|c| vec![c]).collect(),
+		}
+	}
+
+	/// Create a ballot that can have equal or skipped rankings.
+	pub fn with_ranks(weight: usize, ranks: Vec<Vec<usize>>) -> Ballot {
+		Ballot {
+			weight,
+			ranks,
+		}
+	}
+
+	/// Iterate over all ranked candidates in order of preference, equally
+	/// ranked candidates in the order they are given.
+	pub fn candidates<'a>(&'a self) -> impl Iterator<Item = usize> + 'a {
+		self.ranks.iter().flat_map(|r| r.iter().cloned())
+	}
+
+	/// The rank of the candidate, or None if the candidate is not ranked.
+	/// Skipped rankings are not counted.
+	pub fn rank_of(&self, candidate: usize) -> Option<usize> {
+		self.ranks.iter().filter(|r| !r.is_empty()).position(|r| r.contains(&candidate))
+	}
+
+	/// Whether candidate a is ranked higher than candidate b.
+	pub fn prefers(&self, a: usize, b: usize) -> bool {
+		match (self.rank_of(a), self.rank_of(b)) {
+			(Some(ra), Some(rb)) => ra < rb,
+			(Some(_), None) => true,
+			(None, _) => false,
+		}
+	}
+
+	/// Whether no candidates are ranked equally.
+	pub fn is_strict(&self) -> bool {
+		self.ranks.iter().all(|r| r.len() <= 1)
+	}
+
+	/// The candidates in order of preference, skipping repeated candidates and
+	/// skipped rankings, or None if some candidates are ranked equally.
+	pub fn strict_order(&self) -> Option<Vec<usize>> {
+		if !self.is_strict() {
+			return None;
+		}
+		let mut order = Vec::with_capacity(self.ranks.len());
+		for c in self.candidates() {
+			if !order.contains(&c) {
+				order.push(c);
+			}
+		}
+		Some(order)
+	}
+}
+
+/// The reasons a ballot can be invalid for a profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BallotError {
+	/// The ballot ranks a candidate that does not exist.
+	InvalidCandidate(usize),
+	/// The ballot doesn't score every candidate.
+	WrongNumberOfScores(usize),
+	/// The ballot gives a candidate a score above the maximum.
+	InvalidScore(usize),
+}
+
+impl fmt::Display for BallotError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BallotError::InvalidCandidate(c) => write!(f, "invalid candidate {} on ballot", c),
+			BallotError::WrongNumberOfScores(n) => write!(f, "{} scores on ballot", n),
+			BallotError::InvalidScore(c) => {
+				write!(f, "invalid score for candidate {} on ballot", c)
+			}
+		}
+	}
+}
+
+impl Error for BallotError {}
+
+/// The ranked ballots cast for a set of candidates. Candidates are identified
+/// by their index in the list of candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Profile {
+	candidates: Vec<String>,
+	ballots: Vec<Ballot>,
+}
+
+impl Profile {
+	/// Create an empty profile for the candidates with the given names.
+	pub fn new(candidates: Vec<String>) -> Profile {
+		Profile {
+			candidates,
+			ballots: Vec::new(),
+		}
+	}
+
+	/// Create a profile with the given ballots.
+	pub fn with_ballots(
+		candidates: Vec<String>,
+		ballots: Vec<Ballot>,
+	) -> Result<Profile, BallotError> {
+		let mut profile = Profile::new(candidates);
+		for ballot in ballots.into_iter() {
+			profile.add(ballot)?;
+		}
+		Ok(profile)
+	}
+
+	/// Add a ballot to the profile.
+	pub fn add(&mut self, ballot: Ballot) -> Result<(), BallotError> {
+		if let Some(c) = ballot.candidates().find(|c| *c >= self.candidates.len()) {
+			return Err(BallotError::InvalidCandidate(c));
+		}
+		self.ballots.push(ballot);
+		Ok(())
+	}
+
+	/// The names of the candidates.
+	pub fn candidates(&self) -> &[String] {
+		&self.candidates
+	}
+
+	/// The number of candidates.
+	pub fn nb_candidates(&self) -> usize {
+		self.candidates.len()
+	}
+
+	/// Find a candidate by name.
+	pub fn candidate(&self, name: &str) -> Option<usize> {
+		self.candidates.iter().position(|c| c == name)
+	}
+
+	/// The ballots in the profile.
+	pub fn ballots(&self) -> &[Ballot] {
+		&self.ballots
+	}
+
+	/// The total weight of all ballots.
+	pub fn nb_voters(&self) -> usize {
+		self.ballots.iter().map(|b| b.weight).sum()
+	}
+}
+
+/// An approval ballot, approving any number of candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApprovalBallot {
+	/// The number of voters that cast this ballot.
+	pub weight: usize,
+	approved: Vec<usize>,
+}
+
+impl ApprovalBallot {
+	/// Create a ballot approving the given candidates.
+	pub fn new(weight: usize, mut approved: Vec<usize>) -> ApprovalBallot {
+		approved.sort();
+		approved.dedup();
+		ApprovalBallot {
+			weight,
+			approved,
+		}
+	}
+
+	/// The approved candidates in increasing order, without duplicates.
+	pub fn approved(&self) -> &[usize] {
+		&self.approved
+	}
+
+	/// Whether the candidate is approved.
+	pub fn approves(&self, candidate: usize) -> bool {
+		self.approved.binary_search(&candidate).is_ok()
+	}
+}
+
+/// The approval ballots cast for a set of candidates. Candidates are
+/// identified by their index in the list of candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApprovalProfile {
+	candidates: Vec<String>,
+	ballots: Vec<ApprovalBallot>,
+}
+
+impl ApprovalProfile {
+	/// Create an empty profile for the candidates with the given names.
+	pub fn new(candidates: Vec<String>) -> ApprovalProfile {
+		ApprovalProfile {
+			candidates,
+			ballots: Vec::new(),
+		}
+	}
+
+	/// Create a profile with the given ballots.
+	pub fn with_ballots(
+		candidates: Vec<String>,
+		ballots: Vec<ApprovalBallot>,
+	) -> Result<ApprovalProfile, BallotError> {
+		let mut profile = ApprovalProfile::new(candidates);
+		for ballot in ballots.into_iter() {
+			profile.add(ballot)?;
+		}
+		Ok(profile)
+	}
+
+	/// Add a ballot to the profile.
+	pub fn add(&mut self, ballot: ApprovalBallot) -> Result<(), BallotError> {
+		if let Some(c) = ballot.approved.iter().find(|c| **c >= self.candidates.len()) {
+			return Err(BallotError::InvalidCandidate(*c));
+		}
+		self.ballots.push(ballot);
+		Ok(())
+	}
+
+	/// The names of the candidates.
+	pub fn candidates(&self) -> &[String] {
+		&self.candidates
+	}
+
+	/// The number of candidates.
+	pub fn nb_candidates(&self) -> usize {
+		self.candidates.len()
+	}
+
+	/// Find a candidate by name.
+	pub fn candidate(&self, name: &str) -> Option<usize> {
+		self.candidates.iter().position(|c| c == name)
+	}
+
+	/// The ballots in the profile.
+	pub fn ballots(&self) -> &[ApprovalBallot] {
+		&self.ballots
+	}
+
+	/// The total weight of all ballots.
+	pub fn nb_voters(&self) -> usize {
+		self.ballots.iter().map(|b| b.weight).sum()
+	}
+
+	/// The number of voters approving every candidate.
+	pub fn approvals(&self) -> Vec<usize> {
+		let mut approvals = vec![0; self.candidates.len()];
+		for ballot in self.ballots.iter() {
+			for c in ballot.approved.iter() {
+				approvals[*c] += ballot.weight;
+			}
+		}
+		approvals
+	}
+}
+
+/// A rated ballot, giving every candidate a score.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RatedBallot {
+	/// The number of voters that cast this ballot.
+	pub weight: usize,
+	/// The score of every candidate.
+	pub scores: Vec<usize>,
+}
+
+impl RatedBallot {
+	pub fn new(weight: usize, scores: Vec<usize>) -> RatedBallot {
+		RatedBallot {
+			weight,
+			scores,
+		}
+	}
+}
+
+/// The rated ballots cast for a set of candidates, with scores from zero up to
+/// a maximum score. Candidates are identified by their index in the list of
+/// candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RatedProfile {
+	candidates: Vec<String>,
+	max_score: usize,
+	ballots: Vec<RatedBallot>,
+}
+
+impl RatedProfile {
+	/// Create an empty profile for the candidates with the given names.
+	pub fn new(candidates: Vec<String>, max_score: usize) -> RatedProfile {
+		RatedProfile {
+			candidates,
+			max_score,
+			ballots: Vec::new(),
+		}
+	}
+
+	/// Create a profile with the given ballots.
+	pub fn with_ballots(
+		candidates: Vec<String>,
+		max_score: usize,
+		ballots: Vec<RatedBallot>,
+	) -> Result<RatedProfile, BallotError> {
+		let mut profile = RatedProfile::new(candidates, max_score);
+		for ballot in ballots.into_iter() {
+			profile.add(ballot)?;
+		}
+		Ok(profile)
+	}
+
+	/// Add a ballot to the profile.
+	pub fn add(&mut self, ballot: RatedBallot) -> Result<(), BallotError> {
+		if ballot.scores.len() != self.candidates.len() {
+			return Err(BallotError::WrongNumberOfScores(ballot.scores.len()));
+		}
+		if let Some(c) = ballot.scores.iter().position(|s| *s > self.max_score) {
+			return Err(BallotError::InvalidScore(c));
+		}
+		self.ballots.push(ballot);
+		Ok(())
+	}
+
+	/// The names of the candidates.
+	pub fn candidates(&self) -> &[String] {
+		&self.candidates
+	}
+
+	/// The number of candidates.
+	pub fn nb_candidates(&self) -> usize {
+		self.candidates.len()
+	}
+
+	/// The highest score a candidate can receive.
+	pub fn max_score(&self) -> usize {
+		self.max_score
+	}
+
+	/// The ballots in the profile.
+	pub fn ballots(&self) -> &[RatedBallot] {
+		&self.ballots
+	}
+
+	/// The total weight of all ballots.
+	pub fn nb_voters(&self) -> usize {
+		self.ballots.iter().map(|b| b.weight).sum()
+	}
+
+	/// The number of voters giving each score, from zero up to the maximum
+	/// score, to the candidate.
+	pub fn distribution(&self, candidate: usize) -> Vec<usize> {
+		let mut distribution = vec![0; self.max_score + 1];
+		for ballot in self.ballots.iter() {
+			distribution[ballot.scores[candidate]] += ballot.weight;
+		}
+		distribution
+	}
+}
+
+/// Profiles for tests, with the candidates named by their index.
+#[cfg(test)]
+pub(crate) mod fixtures {
+	use super::*;
+
+	/// A profile of the given ranked ballots.
+	pub fn profile(nb_candidates: usize, ballots: Vec<Ballot>) -> Profile {
+		let candidates = (0..nb_candidates).map(|c| c.to_string()).collect();
+		Profile::with_ballots(candidates, ballots).unwrap()
+	}
+
+	/// A profile of approval ballots given by their weight and the approved
+	/// candidates.
+	pub fn approval_profile(
+		nb_candidates: usize,
+		ballots: Vec<(usize, Vec<usize>)>,
+	) -> ApprovalProfile {
+		let candidates = (0..nb_candidates).map(|c| c.to_string()).collect();
+		let ballots = ballots.into_iter().map(|(w, a)| ApprovalBallot::new(w, a)).collect();
+		ApprovalProfile::with_ballots(candidates, ballots).unwrap()
+	}
+
+	/// A profile of rated ballots given by their weight and the scores.
+	pub fn rated_profile(max_score: usize, ballots: Vec<(usize, Vec<usize>)>) -> RatedProfile {
+		let candidates = (0..ballots[0].1.len()).map(|c| c.to_string()).collect();
+		let ballots = ballots.into_iter().map(|(w, s)| RatedBallot::new(w, s)).collect();
+		RatedProfile::with_ballots(candidates, max_score, ballots).unwrap()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ranks() {
+		// a > (skipped) > b = c, d is not ranked.
+		let ballot = Ballot::with_ranks(1, vec![vec![0], vec![], vec![1, 2]]);
+		assert_eq!(Some(0), ballot.rank_of(0));
+		assert_eq!(Some(1), ballot.rank_of(2));
+		assert_eq!(None, ballot.rank_of(3));
+		assert!(ballot.prefers(0, 1));
+		assert!(ballot.prefers(2, 3));
+		assert!(!ballot.prefers(1, 2));
+		assert!(!ballot.prefers(3, 0));
+		assert!(!ballot.is_strict());
+		assert_eq!(None, ballot.strict_order());
+
+		let ballot = Ballot::with_ranks(1, vec![vec![2], vec![], vec![0], vec![2]]);
+		assert_eq!(Some(vec![2, 0]), ballot.strict_order());
+		assert_eq!(Some(0), ballot.rank_of(2));
+	}
+
+	#[test]
+	fn profile() {
+		let names = vec!["a".to_owned(), "b".to_owned()];
+		let mut profile = Profile::new(names);
+		assert_eq!(Ok(()), profile.add(Ballot::new(3, vec![1, 0])));
+		assert_eq!(Err(BallotError::InvalidCandidate(2)), profile.add(Ballot::new(1, vec![2])));
+		assert_eq!(Ok(()), profile.add(Ballot::new(2, vec![0])));
+		assert_eq!(5, profile.nb_voters());
+		assert_eq!(Some(1), profile.candidate("b"));
+		assert_eq!(2, profile.ballots().len());
+	}
+
+	#[test]
+	fn approval_profile() {
+		let names = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+		let mut profile = ApprovalProfile::new(names);
+		let ballot = ApprovalBallot::new(2, vec![2, 0, 2]);
+		assert_eq!(&[0, 2], ballot.approved());
+		assert!(ballot.approves(2));
+		assert!(!ballot.approves(1));
+		assert_eq!(Ok(()), profile.add(ballot));
+		assert_eq!(Ok(()), profile.add(ApprovalBallot::new(1, vec![1, 2])));
+		assert_eq!(
+			Err(BallotError::InvalidCandidate(3)),
+			profile.add(ApprovalBallot::new(1, vec![3]))
+		);
+		assert_eq!(vec![2, 1, 3], profile.approvals());
+		assert_eq!(3, profile.nb_voters());
+	}
+
+	#[test]
+	fn rated_profile() {
+		let names = vec!["a".to_owned(), "b".to_owned()];
+		let mut profile = RatedProfile::new(names, 5);
+		assert_eq!(Ok(()), profile.add(RatedBallot::new(2, vec![5, 1])));
+		assert_eq!(Ok(()), profile.add(RatedBallot::new(1, vec![0, 1])));
+		assert_eq!(
+			Err(BallotError::WrongNumberOfScores(1)),
+			profile.add(RatedBallot::new(1, vec![3]))
+		);
+		assert_eq!(Err(BallotError::InvalidScore(1)), profile.add(RatedBallot::new(1, vec![3, 6])));
+		assert_eq!(vec![1, 0, 0, 0, 0, 2], profile.distribution(0));
+		assert_eq!(3, profile.nb_voters());
+	}
+}
@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+use super::{big, to_big, AllocationError};
+use highest_averages::HighestAverages;
+
+/// The outcome of a biproportional allocation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BiproportionalAllocation {
+	/// The seats per party from the upper apportionment.
+	pub party_seats: Vec<usize>,
+	/// The seats per party and district, with a row per party.
+	pub seats: Vec<Vec<usize>>,
+	/// The divisor of every party. The seats of a party in a district are its
+	/// votes divided by both the party and district divisor, rounded by the
+	/// method. For squared methods, the divisors apply to the squared votes.
+	pub party_divisors: Vec<BigRational>,
+	/// The divisor of every district.
+	pub district_divisors: Vec<BigRational>,
+	/// The number of iterations of the alternating scaling.
+	pub iterations: usize,
+}
+
+/// Implements biproportional apportionment, allocating seats proportionally
+/// to the parties nationally and to the districts locally, as used in Zurich.
+/// The upper apportionment allocates the seats to the parties by their total
+/// votes, the lower apportionment distributes them over the districts by
+/// alternating scaling, rounding with the same method.
+/// For more info: https://en.wikipedia.org/wiki/Biproportional_apportionment
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Biproportional {
+	method: HighestAverages,
+	max_iterations: usize,
+}
+
+impl Biproportional {
+	pub fn new(method: HighestAverages) -> Biproportional {
+		Biproportional {
+			method,
+			max_iterations: 100,
+		}
+	}
+
+	/// Set the number of iterations of the alternating scaling after which the
+	/// allocation fails with [AllocationError::NotConverged]. The default is
+	/// 100.
+	pub fn with_max_iterations(self, max_iterations: usize) -> Biproportional {
+		Biproportional {
+			max_iterations,
+			..self
+		}
+	}
+
+	/// Allocate the seats of every district given the votes per party and
+	/// district, with a row per party. Fails with
+	/// [AllocationError::MismatchedVotes] if a row doesn't have votes for
+	/// every district.
+	pub fn allocate(
+		&self,
+		votes: &[Vec<usize>],
+		district_seats: &[usize],
+	) -> Result<BiproportionalAllocation, AllocationError> {
+		if votes.iter().any(|row| row.len() != district_seats.len()) {
+			return Err(AllocationError::MismatchedVotes);
+		}
+		let sum = |values: &[usize]| {
+			values
+				.iter()
+				.try_fold(0usize, |sum, v| sum.checked_add(*v))
+				.ok_or(AllocationError::Overflow)
+		};
+		let totals: Vec<usize> = votes.iter().map(|row| sum(row)).collect::<Result<_, _>>()?;
+		let party_seats = self.method.allocate(sum(district_seats)?, &totals)?.seats;
+
+		let weights: Vec<Vec<BigRational>> = votes
+			.iter()
+			.map(|row| {
+				row.iter()
+					.map(|v| {
+						if self.method.method.is_squared() {
+							big(*v) * big(*v)
+						} else {
+							big(*v)
+						}
+					})
+					.collect()
+			})
+			.collect();
+		let mut party_divisors = vec![BigRational::one(); votes.len()];
+		let mut district_divisors = vec![BigRational::one(); district_seats.len()];
+		let mut seats = vec![vec![0; district_seats.len()]; votes.len()];
+		for iteration in 1..=self.max_iterations {
+			// Fit the districts, given the party divisors.
+			for (j, nb_seats) in district_seats.iter().enumerate() {
+				let column: Vec<BigRational> =
+					weights.iter().zip(party_divisors.iter()).map(|(w, d)| &w[j] / d).collect();
+				let (allocated, divisor) = self.apportion(&column, *nb_seats)?;
+				for (row, s) in seats.iter_mut().zip(allocated) {
+					row[j] = s;
+				}
+				district_divisors[j] = divisor;
+			}
+			if seats.iter().zip(party_seats.iter()).all(|(row, s)| row.iter().sum::<usize>() == *s)
+			{
+				return Ok(BiproportionalAllocation {
+					party_seats,
+					seats,
+					party_divisors,
+					district_divisors,
+					iterations: iteration,
+				});
+			}
+
+			// Fit the parties, given the district divisors.
+			for (i, nb_seats) in party_seats.iter().enumerate() {
+				let row: Vec<BigRational> =
+					weights[i].iter().zip(district_divisors.iter()).map(|(w, d)| w / d).collect();
+				let (allocated, divisor) = self.apportion(&row, *nb_seats)?;
+				seats[i] = allocated;
+				party_divisors[i] = divisor;
+			}
+			let fits = district_seats
+				.iter()
+				.enumerate()
+				.all(|(j, s)| seats.iter().map(|row| row[j]).sum::<usize>() == *s);
+			if fits {
+				return Ok(BiproportionalAllocation {
+					party_seats,
+					seats,
+					party_divisors,
+					district_divisors,
+					iterations: iteration,
+				});
+			}
+		}
+		Err(AllocationError::NotConverged)
+	}
+
+	/// Allocate the seats proportionally to the given weights and find a
+	/// divisor for which rounding the weights gives these seats. Ties are
+	/// broken in favour of the entry listed first.
+	fn apportion(
+		&self,
+		weights: &[BigRational],
+		nb_seats: usize,
+	) -> Result<(Vec<usize>, BigRational), AllocationError> {
+		let divisors: Vec<BigRational> = self
+			.method
+			.divisors()
+			.take(nb_seats + 1)
+			.map(|d| d.map(to_big))
+			.collect::<Result<_, _>>()?;
+
+		// The averages of every entry for every seat it could get, where None
+		// is the average for a divisor of zero.
+		let mut averages: Vec<(usize, Option<BigRational>)> = Vec::new();
+		for (i, weight) in weights.iter().enumerate().filter(|(_, w)| w.is_positive()) {
+			for divisor in divisors.iter() {
+				let average = if divisor.is_zero() {
+					None
+				} else {
+					Some(weight / divisor)
+				};
+				averages.push((i, average));
+			}
+		}
+		if averages.is_empty() {
+			if nb_seats > 0 {
+				return Err(AllocationError::NoVotes);
+			}
+			return Ok((vec![0; weights.len()], BigRational::one()));
+		}
+		averages.sort_by(|a, b| match (&a.1, &b.1) {
+			(None, None) => Ordering::Equal,
+			(None, Some(_)) => Ordering::Less,
+			(Some(_), None) => Ordering::Greater,
+			(Some(x), Some(y)) => y.cmp(x),
+		});
+
+		// The next average is needed for the divisor, but a custom sequence
+		// may run out of divisors before that.
+		if averages.len() <= nb_seats {
+			return Err(AllocationError::Infeasible);
+		}
+		let mut seats = vec![0; weights.len()];
+		for (i, _) in averages[..nb_seats].iter() {
+			seats[*i] += 1;
+		}
+		let last = if nb_seats > 0 {
+			averages[nb_seats - 1].1.clone()
+		} else {
+			None
+		};
+		let divisor = match (last, &averages[nb_seats].1) {
+			(Some(last), Some(next)) => (last + next) / big(2),
+			(None, Some(next)) => next * big(2),
+			// More entries need a seat for a divisor of zero than there are
+			// seats, which no divisor can fix.
+			(_, None) => return Err(AllocationError::Infeasible),
+		};
+		Ok((seats, divisor))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::{Method, Sequence};
+	use num_bigint::BigInt;
+
+	#[test]
+	fn example_wikipedia() {
+		let votes = vec![vec![123, 45, 815], vec![912, 714, 414], vec![312, 255, 215]];
+		let district_seats = vec![7, 5, 8];
+		let biproportional = Biproportional::new(HighestAverages::new(Method::SainteLague));
+		let result = biproportional.allocate(&votes, &district_seats).unwrap();
+		assert_eq!(vec![5, 11, 4], result.party_seats);
+		assert_eq!(vec![vec![1, 0, 4], vec![4, 4, 3], vec![2, 1, 1]], result.seats);
+
+		// Every seat count is the votes divided by both divisors, rounded.
+		for (i, row) in votes.iter().enumerate() {
+			for (j, v) in row.iter().enumerate() {
+				let quotient = big(*v) / (&result.party_divisors[i] * &result.district_divisors[j]);
+				let rounded = ((quotient + big(1)) / big(2)).floor().to_integer();
+				assert_eq!(BigInt::from(result.seats[i][j]), rounded);
+			}
+		}
+	}
+
+	#[test]
+	fn infeasible() {
+		// A district with seats but without votes can't be filled.
+		let votes = vec![vec![10, 0], vec![20, 0]];
+		let dhondt = Biproportional::new(HighestAverages::new(Method::DHondt));
+		assert_eq!(Err(AllocationError::NoVotes), dhondt.allocate(&votes, &[2, 1]));
+
+		// Every party gets a first seat in every district where it has votes.
+		let votes = vec![vec![10, 5], vec![20, 5], vec![30, 5]];
+		let huntington_hill = Biproportional::new(HighestAverages::new(Method::HuntingtonHill));
+		assert_eq!(Err(AllocationError::Infeasible), huntington_hill.allocate(&votes, &[4, 2]));
+
+		// The custom divisors run out.
+		let given = Sequence::Given(&[(1, 1), (2, 1), (3, 1)]);
+		let custom = Biproportional::new(HighestAverages::new(Method::Custom(given)));
+		let votes = vec![vec![100, 0], vec![0, 100]];
+		assert_eq!(Err(AllocationError::Infeasible), custom.allocate(&votes, &[3, 3]));
+	}
+
+	#[test]
+	fn invalid_input() {
+		let sainte_lague = Biproportional::new(HighestAverages::new(Method::SainteLague));
+		let votes = vec![vec![10, 5], vec![20]];
+		assert_eq!(Err(AllocationError::MismatchedVotes), sainte_lague.allocate(&votes, &[2, 1]));
+		let votes = vec![vec![usize::MAX, 5], vec![20, 5]];
+		assert_eq!(Err(AllocationError::Overflow), sainte_lague.allocate(&votes, &[2, 1]));
+
+		// Scaling needs more than one iteration for the Wikipedia example.
+		let votes = vec![vec![123, 45, 815], vec![912, 714, 414], vec![312, 255, 215]];
+		let once = sainte_lague.with_max_iterations(1);
+		assert_eq!(Err(AllocationError::NotConverged), once.allocate(&votes, &[7, 5, 8]));
+	}
+}
@@ -0,0 +1,218 @@
+use super::{AllocateSeats, AllocationError};
+
+/// The minimum and maximum number of seats of every party.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bounds {
+	/// The minimum number of seats per party.
+	pub min: Vec<usize>,
+	/// The maximum number of seats per party, None if there is no maximum.
+	pub max: Vec<Option<usize>>,
+}
+
+impl Bounds {
+	/// The same bounds for the given number of parties.
+	pub fn uniform(nb_parties: usize, min: usize, max: Option<usize>) -> Bounds {
+		Bounds {
+			min: vec![min; nb_parties],
+			max: vec![max; nb_parties],
+		}
+	}
+
+	/// Check that the given number of seats can be allocated within the bounds.
+	/// Fails with [AllocationError::MismatchedVotes] if there are no bounds for
+	/// every party.
+	pub(crate) fn check(&self, nb_seats: usize, nb_parties: usize) -> Result<(), AllocationError> {
+		if self.min.len() != nb_parties || self.max.len() != nb_parties {
+			return Err(AllocationError::MismatchedVotes);
+		}
+		if nb_parties == 0 {
+			return Err(AllocationError::NoParties);
+		}
+		let min: usize = self.min.iter().sum();
+		let unbounded = self.max.iter().any(|m| m.is_none());
+		let max: usize = self.max.iter().map(|m| m.unwrap_or(0)).sum();
+		let crossed = self.min.iter().zip(self.max.iter()).any(|(a, b)| b.is_some_and(|b| *a > b));
+		if crossed || nb_seats < min || (!unbounded && nb_seats > max) {
+			return Err(AllocationError::Infeasible);
+		}
+		Ok(())
+	}
+}
+
+/// A bound that determined the number of seats of a party.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+	Min,
+	Max,
+}
+
+/// The outcome of a seat allocation within bounds.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoundedAllocation {
+	/// The number of seats per party.
+	pub seats: Vec<usize>,
+	/// The bound of every party that determined its seats, if any.
+	pub binding: Vec<Option<Binding>>,
+}
+
+/// Allocates seats with another method within a minimum and maximum number of
+/// seats per party. Parties that get more seats than their maximum, or if
+/// there are none, fewer seats than their minimum are given their bound, and
+/// the remaining seats are allocated among the other parties, until no bound
+/// is violated. Fixing parties at their minimum leaves fewer seats for the
+/// others, so the maximums are derived again after that. This suits quota
+/// methods; divisor methods are allocated within bounds exactly by
+/// `HighestAverages::allocate_bounded`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bounded<A> {
+	allocator: A,
+	bounds: Bounds,
+}
+
+impl<A: AllocateSeats> Bounded<A> {
+	pub fn new(allocator: A, bounds: Bounds) -> Bounded<A> {
+		Bounded {
+			allocator,
+			bounds,
+		}
+	}
+
+	/// Calculates the seats per party and reports which bounds were binding.
+	pub fn allocate(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<BoundedAllocation, AllocationError> {
+		self.bounds.check(nb_seats, parties.len())?;
+		let mut binding: Vec<Option<Binding>> = vec![None; parties.len()];
+		loop {
+			let fixed: usize = (0..parties.len())
+				.filter_map(|p| match binding[p] {
+					Some(Binding::Min) => Some(self.bounds.min[p]),
+					Some(Binding::Max) => self.bounds.max[p],
+					None => None,
+				})
+				.sum();
+			let free: Vec<usize> = (0..parties.len())
+				.map(|p| {
+					if binding[p].is_none() {
+						parties[p]
+					} else {
+						0
+					}
+				})
+				.collect();
+			let mut seats = self.allocator.try_allocate_seats(nb_seats - fixed, &free)?;
+
+			// Fix the parties above their maximum first, as the seats they lose
+			// may lift others to their minimum.
+			let over: Vec<usize> = (0..parties.len())
+				.filter(|p| {
+					binding[*p].is_none() && self.bounds.max[*p].is_some_and(|m| seats[*p] > m)
+				})
+				.collect();
+			let under: Vec<usize> = (0..parties.len())
+				.filter(|p| binding[*p].is_none() && seats[*p] < self.bounds.min[*p])
+				.collect();
+			let violated = !over.is_empty() || !under.is_empty();
+			if !over.is_empty() {
+				for p in over {
+					binding[p] = Some(Binding::Max);
+				}
+			} else if !under.is_empty() {
+				for p in under {
+					binding[p] = Some(Binding::Min);
+				}
+				// The parties fixed at their minimum take seats from the others,
+				// so the maximums may no longer be reached.
+				for b in binding.iter_mut().filter(|b| **b == Some(Binding::Max)) {
+					*b = None;
+				}
+			}
+			for p in 0..parties.len() {
+				match binding[p] {
+					Some(Binding::Min) => seats[p] = self.bounds.min[p],
+					Some(Binding::Max) => seats[p] = self.bounds.max[p].unwrap(),
+					None => {}
+				}
+			}
+			if !violated {
+				return Ok(BoundedAllocation {
+					seats,
+					binding,
+				});
+			}
+		}
+	}
+}
+
+impl<A: AllocateSeats> AllocateSeats for Bounded<A> {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		self.allocate(nb_seats, parties).map(|a| a.seats)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::{HighestAverages, Method};
+	use largest_remainder::{LargestRemainder, Quota};
+
+	#[test]
+	fn quota_bounds() {
+		let parties = [7000, 2000, 600, 400];
+		let hare = LargestRemainder::new(Quota::Hare);
+		assert_eq!(vec![7, 2, 1, 0], hare.allocate_seats(10, parties.to_vec()));
+
+		let bounds = Bounds {
+			min: vec![0, 0, 0, 1],
+			max: vec![Some(6), None, None, None],
+		};
+		let bounded = Bounded::new(hare, bounds).allocate(10, &parties).unwrap();
+		assert_eq!(vec![6, 2, 1, 1], bounded.seats);
+		assert_eq!(vec![Some(Binding::Max), None, None, Some(Binding::Min)], bounded.binding);
+
+		// The seats above the maximum go to the other parties.
+		let bounds = Bounds::uniform(4, 1, Some(5));
+		let bounded = Bounded::new(hare, bounds).allocate(10, &parties).unwrap();
+		assert_eq!(vec![5, 3, 1, 1], bounded.seats);
+
+		let bounded = Bounded::new(hare, Bounds::uniform(4, 3, None));
+		assert_eq!(Err(AllocationError::Infeasible), bounded.try_allocate_seats(10, &parties));
+
+		// The minimums leave the largest party fewer seats than its maximum.
+		let bounds = Bounds {
+			min: vec![0, 3, 3],
+			max: vec![Some(8), None, None],
+		};
+		let bounded = Bounded::new(hare, bounds).allocate(10, &[100000, 1, 1]).unwrap();
+		assert_eq!(vec![4, 3, 3], bounded.seats);
+		assert_eq!(vec![None, Some(Binding::Min), Some(Binding::Min)], bounded.binding);
+	}
+
+	#[test]
+	fn divisor_bounds() {
+		// The minimums keep the first party below the maximum it was fixed at.
+		let parties = [918, 244, 57, 535, 97];
+		let bounds = Bounds {
+			min: vec![2, 1, 0, 0, 2],
+			max: vec![Some(4), Some(5), None, None, None],
+		};
+		let dhondt = HighestAverages::new(Method::DHondt);
+		let exact = dhondt.allocate_bounded(8, &parties, &bounds).unwrap();
+		let bounded = Bounded::new(dhondt, bounds).allocate(8, &parties).unwrap();
+		assert_eq!(vec![3, 1, 0, 2, 2], bounded.seats);
+		assert_eq!(exact.seats, bounded.seats);
+		assert_eq!(None, bounded.binding[0]);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let bounded = Bounded::new(LargestRemainder::new(Quota::Hare), Bounds::uniform(3, 0, None));
+		assert_eq!(Err(AllocationError::MismatchedVotes), bounded.try_allocate_seats(5, &[10, 20]));
+	}
+}
@@ -0,0 +1,456 @@
+use std::cmp::Reverse;
+use std::error::Error;
+use std::fmt;
+
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use super::big;
+use ballot::{ApprovalBallot, ApprovalProfile};
+use equal_shares;
+use phragmen::{self, PhragmenResult};
+
+/// A project that can be funded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Project {
+	pub id: String,
+	pub cost: usize,
+	pub name: Option<String>,
+}
+
+/// A participatory budgeting election, with approval ballots for projects.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instance {
+	/// The total budget that can be spent.
+	pub budget: usize,
+	pub projects: Vec<Project>,
+	/// The approval ballots, where the candidates are the projects.
+	pub profile: ApprovalProfile,
+	/// The key-value pairs of the META section of a Pabulib file.
+	pub meta: Vec<(String, String)>,
+}
+
+/// The reasons a Pabulib file can be invalid.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PabulibError {
+	/// A required section is missing.
+	MissingSection(&'static str),
+	/// A required column or META key is missing.
+	MissingField(&'static str),
+	/// The line with the given number can't be parsed.
+	InvalidLine(usize),
+	/// A vote is for a project that is not listed.
+	UnknownProject(String),
+	/// The votes are of a type other than approval.
+	UnsupportedVoteType(String),
+}
+
+impl fmt::Display for PabulibError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PabulibError::MissingSection(s) => write!(f, "missing section {}", s),
+			PabulibError::MissingField(s) => write!(f, "missing field {}", s),
+			PabulibError::InvalidLine(n) => write!(f, "invalid line {}", n),
+			PabulibError::UnknownProject(ref id) => write!(f, "vote for unknown project {}", id),
+			PabulibError::UnsupportedVoteType(ref t) => write!(f, "unsupported vote type {}", t),
+		}
+	}
+}
+
+impl Error for PabulibError {}
+
+/// A section of a Pabulib file, with the line numbers and fields of its rows.
+struct Section {
+	header: Vec<String>,
+	rows: Vec<(usize, Vec<String>)>,
+}
+
+impl Section {
+	/// The index of the column with the given name.
+	fn column(&self, name: &'static str) -> Result<usize, PabulibError> {
+		self.header.iter().position(|h| h == name).ok_or(PabulibError::MissingField(name))
+	}
+}
+
+/// Split a line by semicolons, allowing fields to be quoted.
+fn split(line: &str) -> Vec<String> {
+	let mut fields = Vec::new();
+	let mut field = String::new();
+	let mut quoted = false;
+	let mut chars = line.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if quoted && chars.peek() == Some(&'"') => {
+				field.push('"');
+				chars.next();
+			}
+			'"' => quoted = !quoted,
+			';' if !quoted => fields.push(std::mem::take(&mut field)),
+			c => field.push(c),
+		}
+	}
+	fields.push(field);
+	fields.into_iter().map(|f| f.trim().to_owned()).collect()
+}
+
+/// Parse a whole amount, which some files write with decimals.
+fn amount(s: &str) -> Option<usize> {
+	match s.find('.') {
+		Some(i) if s[i + 1..].chars().all(|c| c == '0') => s[..i].parse().ok(),
+		Some(_) => None,
+		None => s.parse().ok(),
+	}
+}
+
+impl Instance {
+	/// Read an election in the Pabulib format, with sections META, PROJECTS
+	/// and VOTES. Only approval votes are supported, which is assumed if the
+	/// vote type is not given.
+	/// For more info: https://pabulib.org/format
+	pub fn from_pabulib(input: &str) -> Result<Instance, PabulibError> {
+		let mut sections: Vec<(String, Section)> = Vec::new();
+		for (i, line) in input.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() {
+				continue;
+			}
+			let name = line.to_uppercase();
+			if name == "META" || name == "PROJECTS" || name == "VOTES" {
+				sections.push((
+					name,
+					Section {
+						header: Vec::new(),
+						rows: Vec::new(),
+					},
+				));
+				continue;
+			}
+			let section = match sections.last_mut() {
+				Some(&mut (_, ref mut section)) => section,
+				None => return Err(PabulibError::InvalidLine(i + 1)),
+			};
+			if section.header.is_empty() {
+				section.header = split(line);
+			} else {
+				section.rows.push((i + 1, split(line)));
+			}
+		}
+		let mut take = |name: &'static str| {
+			let i = sections
+				.iter()
+				.position(|s| s.0 == name)
+				.ok_or(PabulibError::MissingSection(name))?;
+			Ok(sections.swap_remove(i).1)
+		};
+		let (meta, projects, votes) = (take("META")?, take("PROJECTS")?, take("VOTES")?);
+
+		let budget = match meta.rows.iter().find(|r| r.1[0] == "budget") {
+			Some(&(n, ref row)) => {
+				row.get(1).and_then(|b| amount(b)).ok_or(PabulibError::InvalidLine(n))?
+			}
+			None => return Err(PabulibError::MissingField("budget")),
+		};
+		let meta: Vec<(String, String)> = meta
+			.rows
+			.into_iter()
+			.map(|(n, row)| match row.len() {
+				2 => Ok((row[0].clone(), row[1].clone())),
+				_ => Err(PabulibError::InvalidLine(n)),
+			})
+			.collect::<Result<_, _>>()?;
+		if let Some((_, vote_type)) = meta.iter().find(|m| m.0 == "vote_type") {
+			if vote_type != "approval" {
+				return Err(PabulibError::UnsupportedVoteType(vote_type.clone()));
+			}
+		}
+
+		let id = projects.column("project_id")?;
+		let cost = projects.column("cost")?;
+		let name = projects.column("name").ok();
+		let projects: Vec<Project> = projects
+			.rows
+			.iter()
+			.map(|&(n, ref row)| {
+				let field = |i: usize| row.get(i).ok_or(PabulibError::InvalidLine(n));
+				Ok(Project {
+					id: field(id)?.clone(),
+					cost: amount(field(cost)?).ok_or(PabulibError::InvalidLine(n))?,
+					name: match name {
+						Some(i) => Some(field(i)?.clone()),
+						None => None,
+					},
+				})
+			})
+			.collect::<Result<_, _>>()?;
+
+		let vote = votes.column("vote")?;
+		let mut profile = ApprovalProfile::new(projects.iter().map(|p| p.id.clone()).collect());
+		for &(n, ref row) in votes.rows.iter() {
+			let mut approved = Vec::new();
+			for id in row.get(vote).ok_or(PabulibError::InvalidLine(n))?.split(',') {
+				let id = id.trim();
+				if id.is_empty() {
+					continue;
+				}
+				match profile.candidate(id) {
+					Some(p) => approved.push(p),
+					None => return Err(PabulibError::UnknownProject(id.to_owned())),
+				}
+			}
+			profile.add(ApprovalBallot::new(1, approved)).unwrap();
+		}
+
+		Ok(Instance {
+			budget,
+			projects,
+			profile,
+			meta,
+		})
+	}
+}
+
+/// The rules to select projects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+	/// Select the projects with the most votes that fit in the budget.
+	GreedyVotes,
+	/// Select the projects with the most votes per cost that fit in the
+	/// budget.
+	GreedyCost,
+	/// The Method of Equal Shares, where every voter receives an equal share
+	/// of the budget and the supporters of a project pay its cost. Voters
+	/// value a project at its cost, so the project that is cheapest per unit
+	/// of cost is selected.
+	EqualShares,
+	/// Sequential Phragmén, where the supporters of a project share its cost
+	/// as a load and the project that leads to the lowest load is selected.
+	/// The selection stops at the first such project that doesn't fit in the
+	/// budget.
+	Phragmen,
+}
+
+/// The selected projects.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Outcome {
+	/// The selected projects in the order they were selected.
+	pub selected: Vec<usize>,
+	/// The total cost of the selected projects.
+	pub spent: usize,
+}
+
+impl Outcome {
+	fn select(&mut self, project: usize, instance: &Instance) {
+		self.selected.push(project);
+		self.spent += instance.projects[project].cost;
+	}
+
+	/// Whether the project is not selected yet and fits in the budget.
+	fn fits(&self, project: usize, instance: &Instance) -> bool {
+		let spent = self.spent.checked_add(instance.projects[project].cost);
+		!self.selected.contains(&project) && spent.is_some_and(|s| s <= instance.budget)
+	}
+}
+
+/// Implements rules for participatory budgeting, where projects have costs
+/// and are selected within a budget. Ties are broken in favour of the lowest
+/// project index.
+/// For more info: https://en.wikipedia.org/wiki/Participatory_budgeting_algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Budgeting {
+	rule: Rule,
+	completion: bool,
+}
+
+impl Budgeting {
+	pub fn new(rule: Rule) -> Budgeting {
+		Budgeting {
+			rule,
+			completion: false,
+		}
+	}
+
+	/// Spend the budget that is left after the rule greedily by votes.
+	pub fn with_completion(self, completion: bool) -> Budgeting {
+		Budgeting {
+			completion,
+			..self
+		}
+	}
+
+	/// Select the projects to fund.
+	pub fn select(&self, instance: &Instance) -> Outcome {
+		let mut outcome = Outcome {
+			selected: Vec::new(),
+			spent: 0,
+		};
+		match self.rule {
+			Rule::GreedyVotes => greedy_votes(instance, &mut outcome),
+			Rule::GreedyCost => {
+				let votes = instance.profile.approvals();
+				let mut order: Vec<usize> = (0..instance.projects.len()).collect();
+				// Compare votes per cost by cross-multiplying.
+				order.sort_by(|a, b| {
+					let cost = |p: usize| instance.projects[p].cost as u128;
+					(votes[*b] as u128 * cost(*a)).cmp(&(votes[*a] as u128 * cost(*b)))
+				});
+				for p in order {
+					if outcome.fits(p, instance) {
+						outcome.select(p, instance);
+					}
+				}
+			}
+			Rule::EqualShares => equal_shares(instance, &mut outcome),
+			Rule::Phragmen => phragmen(instance, &mut outcome),
+		}
+		if self.completion {
+			greedy_votes(instance, &mut outcome);
+		}
+		outcome
+	}
+}
+
+fn greedy_votes(instance: &Instance, outcome: &mut Outcome) {
+	let votes = instance.profile.approvals();
+	let mut order: Vec<usize> = (0..instance.projects.len()).collect();
+	order.sort_by_key(|p| Reverse(votes[*p]));
+	for p in order {
+		if outcome.fits(p, instance) {
+			outcome.select(p, instance);
+		}
+	}
+}
+
+fn costs(instance: &Instance) -> Vec<BigRational> {
+	instance.projects.iter().map(|p| big(p.cost)).collect()
+}
+
+fn equal_shares(instance: &Instance, outcome: &mut Outcome) {
+	let nb_voters = instance.profile.nb_voters();
+	if nb_voters == 0 {
+		return;
+	}
+	let budget = big(instance.budget) / big(nb_voters);
+	for p in equal_shares::buy(&instance.profile, &costs(instance), budget).winners {
+		outcome.select(p, instance);
+	}
+}
+
+fn phragmen(instance: &Instance, outcome: &mut Outcome) {
+	let mut result = PhragmenResult {
+		winners: Vec::new(),
+		loads: vec![BigRational::zero(); instance.profile.ballots().len()],
+	};
+	phragmen::extend(&instance.profile, &costs(instance), &mut result, |winners, p| {
+		let spent = winners.iter().map(|w| instance.projects[*w].cost).sum();
+		let selected = Outcome {
+			selected: winners.to_vec(),
+			spent,
+		};
+		selected.fits(p, instance)
+	});
+	for p in result.winners {
+		outcome.select(p, instance);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EXAMPLE: &str = "META
+key;value
+description;Example
+num_projects;4
+num_votes;10
+budget;1000
+vote_type;approval
+PROJECTS
+project_id;cost;name
+1;600;Park
+2;400;Library
+3;300;\"Bike lanes; north\"
+4;200;Benches
+VOTES
+voter_id;vote
+1;1,2
+2;1,2
+3;1,2
+4;1,2
+5;1,2
+6;1
+7;3
+8;3
+9;3
+10;3,4
+";
+
+	#[test]
+	fn pabulib() {
+		let instance = Instance::from_pabulib(EXAMPLE).unwrap();
+		assert_eq!(1000, instance.budget);
+		assert_eq!(4, instance.projects.len());
+		assert_eq!(Some("Bike lanes; north".to_owned()), instance.projects[2].name);
+		assert_eq!(vec![6, 5, 4, 1], instance.profile.approvals());
+		assert_eq!(("vote_type".to_owned(), "approval".to_owned()), instance.meta[4]);
+
+		let invalid = EXAMPLE.replace("10;3,4", "10;3,5");
+		assert_eq!(
+			Err(PabulibError::UnknownProject("5".to_owned())),
+			Instance::from_pabulib(&invalid)
+		);
+		let invalid = EXAMPLE.replace("VOTES", "");
+		assert_eq!(Err(PabulibError::MissingSection("VOTES")), Instance::from_pabulib(&invalid));
+		let invalid = EXAMPLE.replace("4;200;", "4;two hundred;");
+		assert_eq!(Err(PabulibError::InvalidLine(13)), Instance::from_pabulib(&invalid));
+		let invalid = EXAMPLE.replace("budget;1000", "budget;1000.00");
+		assert_eq!(1000, Instance::from_pabulib(&invalid).unwrap().budget);
+		let invalid = EXAMPLE.replace("budget;1000", "budget;1000.50");
+		assert_eq!(Err(PabulibError::InvalidLine(6)), Instance::from_pabulib(&invalid));
+		let invalid = EXAMPLE.replace("budget;1000\n", "");
+		assert_eq!(Err(PabulibError::MissingField("budget")), Instance::from_pabulib(&invalid));
+		let invalid = EXAMPLE.replace("vote_type;approval", "vote_type;ordinal");
+		assert_eq!(
+			Err(PabulibError::UnsupportedVoteType("ordinal".to_owned())),
+			Instance::from_pabulib(&invalid)
+		);
+	}
+
+	#[test]
+	fn rules() {
+		let instance = Instance::from_pabulib(EXAMPLE).unwrap();
+		let outcome =
+			|rule, completion| Budgeting::new(rule).with_completion(completion).select(&instance);
+		assert_eq!(
+			Outcome {
+				selected: vec![0, 1],
+				spent: 1000,
+			},
+			outcome(Rule::GreedyVotes, false)
+		);
+		assert_eq!(vec![2, 1, 3], outcome(Rule::GreedyCost, false).selected);
+
+		// The park costs each supporter 100 for 600, which is less per unit
+		// of cost than the bike lanes, at 75 for 300.
+		let equal_shares = outcome(Rule::EqualShares, false);
+		assert_eq!(vec![0, 2], equal_shares.selected);
+		assert_eq!(900, equal_shares.spent);
+		assert_eq!(vec![0, 2], outcome(Rule::EqualShares, true).selected);
+
+		// The park has the lowest load after the bike lanes and the library,
+		// but doesn't fit, so the cheaper benches aren't considered.
+		let phragmen = outcome(Rule::Phragmen, false);
+		assert_eq!(vec![2, 1], phragmen.selected);
+		assert_eq!(vec![2, 1, 3], outcome(Rule::Phragmen, true).selected);
+	}
+
+	#[test]
+	fn huge_cost() {
+		let huge = EXAMPLE.replace("1;600;Park", &format!("1;{};Park", usize::MAX));
+		let instance = Instance::from_pabulib(&huge).unwrap();
+		for rule in [Rule::GreedyVotes, Rule::GreedyCost, Rule::EqualShares, Rule::Phragmen].iter()
+		{
+			let outcome = Budgeting::new(*rule).with_completion(true).select(&instance);
+			assert!(!outcome.selected.contains(&0));
+		}
+	}
+}
@@ -0,0 +1,306 @@
+use num_rational::{BigRational, Rational};
+
+use super::{big, checked_sum, to_big, AllocateSeats, AllocationError};
+use highest_averages::{HighestAverages, Method};
+use largest_remainder::{LargestRemainder, Quota};
+
+/// The rules of the national compensation tier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rules {
+	/// The parties get their seats nationally by Sainte-Laguë with a first
+	/// divisor of 7/5. The leveling seats are assigned to the districts where
+	/// the party's next quotient, relative to the votes per seat in the
+	/// district, is highest. Every district gets exactly its own leveling
+	/// seats. The threshold is 4%.
+	Norwegian,
+	/// The parties get their seats nationally by Sainte-Laguë with a first
+	/// divisor of 6/5. The leveling seats are assigned to the districts where
+	/// the party's next quotient is highest. Every district gets exactly its
+	/// own leveling seats. The threshold is 4%.
+	Swedish,
+	/// The parties get their seats nationally by the Hare quota. The leveling
+	/// seats are assigned to the districts where the party's next quotient by
+	/// the Danish divisors is highest, regardless of the leveling seats of the
+	/// districts. The threshold is 2%.
+	Danish,
+}
+
+impl Rules {
+	/// The minimum share of the national votes to get leveling seats.
+	fn threshold(&self) -> Rational {
+		match *self {
+			Rules::Norwegian | Rules::Swedish => Rational::new(4, 100),
+			Rules::Danish => Rational::new(2, 100),
+		}
+	}
+
+	/// The divisors used to assign the leveling seats to the districts.
+	fn divisors(&self) -> HighestAverages {
+		match *self {
+			Rules::Norwegian => {
+				HighestAverages::new(Method::ModifiedSainteLague(Rational::new(7, 5)))
+			}
+			Rules::Swedish => {
+				HighestAverages::new(Method::ModifiedSainteLague(Rational::new(6, 5)))
+			}
+			Rules::Danish => HighestAverages::new(Method::Danish),
+		}
+	}
+
+	/// Allocate the seats nationally.
+	fn allocate(&self, nb_seats: usize, parties: &[usize]) -> Result<Vec<usize>, AllocationError> {
+		match *self {
+			Rules::Norwegian | Rules::Swedish => {
+				self.divisors().try_allocate_seats(nb_seats, parties)
+			}
+			Rules::Danish => {
+				LargestRemainder::new(Quota::Hare).try_allocate_seats(nb_seats, parties)
+			}
+		}
+	}
+}
+
+/// A district with its own seats and leveling seats.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct District {
+	/// The number of seats allocated within the district.
+	pub seats: usize,
+	/// The number of leveling seats assigned to the district.
+	pub leveling_seats: usize,
+	/// The number of votes per party in the district.
+	pub votes: Vec<usize>,
+}
+
+/// The outcome of an election with a compensation tier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompensationResult {
+	/// The seats per party won in every district, with a row per district.
+	pub district_seats: Vec<Vec<usize>>,
+	/// The leveling seats per party assigned to every district.
+	pub leveling_seats: Vec<Vec<usize>>,
+	/// The total number of seats per party.
+	pub seats: Vec<usize>,
+	/// The parties that met the threshold.
+	pub qualified: Vec<bool>,
+	/// The parties that won more district seats than their national share,
+	/// which keep them but get no leveling seats.
+	pub overhang: Vec<usize>,
+}
+
+/// Implements elections in multiple districts with national leveling seats,
+/// which compensate the parties for disproportionalities between the
+/// districts. A party that won more seats in the districts than its national
+/// share keeps them, and the national share of the others is recalculated
+/// without it.
+/// For more info: https://en.wikipedia.org/wiki/Leveling_seat
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Compensation<A> {
+	allocator: A,
+	rules: Rules,
+	threshold: Rational,
+}
+
+impl<A: AllocateSeats> Compensation<A> {
+	/// Allocate the seats within the districts with the given allocator.
+	pub fn new(allocator: A, rules: Rules) -> Compensation<A> {
+		Compensation {
+			allocator,
+			rules,
+			threshold: rules.threshold(),
+		}
+	}
+
+	/// Set the minimum share of the national votes to get leveling seats.
+	pub fn with_threshold(self, threshold: Rational) -> Compensation<A> {
+		Compensation {
+			threshold,
+			..self
+		}
+	}
+
+	/// Allocate the seats of all districts and the leveling seats. Fails with
+	/// [AllocationError::NoParties] if there are no districts, and with
+	/// [AllocationError::MismatchedVotes] if a district doesn't have votes for
+	/// every party.
+	pub fn allocate(&self, districts: &[District]) -> Result<CompensationResult, AllocationError> {
+		let nb_parties = districts.first().ok_or(AllocationError::NoParties)?.votes.len();
+		if districts.iter().any(|d| d.votes.len() != nb_parties) {
+			return Err(AllocationError::MismatchedVotes);
+		}
+
+		let mut district_seats = Vec::with_capacity(districts.len());
+		for district in districts.iter() {
+			district_seats
+				.push(self.allocator.try_allocate_seats(district.seats, &district.votes)?);
+		}
+		let won: Vec<usize> = (0..nb_parties)
+			.map(|p| checked_sum(district_seats.iter().map(|s| s[p])))
+			.collect::<Result<_, _>>()?;
+		let votes: Vec<usize> = (0..nb_parties)
+			.map(|p| checked_sum(districts.iter().map(|d| d.votes[p])))
+			.collect::<Result<_, _>>()?;
+		let total_votes = checked_sum(votes.iter().cloned())?;
+		let qualified: Vec<bool> =
+			votes.iter().map(|v| big(*v) >= to_big(self.threshold) * big(total_votes)).collect();
+		let total_seats = checked_sum(
+			districts.iter().map(|d| d.seats).chain(districts.iter().map(|d| d.leveling_seats)),
+		)?;
+		let district_votes: Vec<usize> = districts
+			.iter()
+			.map(|d| checked_sum(d.votes.iter().cloned()))
+			.collect::<Result<_, _>>()?;
+
+		// Parties with more district seats than their share keep them, and
+		// the share of the others is recalculated without them.
+		let mut overhang = Vec::new();
+		let national = loop {
+			let participates = |p: usize| qualified[p] && !overhang.contains(&p);
+			let kept: usize = (0..nb_parties).filter(|p| !participates(*p)).map(|p| won[p]).sum();
+			let masked: Vec<usize> = (0..nb_parties)
+				.map(|p| {
+					if participates(p) {
+						votes[p]
+					} else {
+						0
+					}
+				})
+				.collect();
+			let national = self.rules.allocate(total_seats.saturating_sub(kept), &masked)?;
+			let over: Vec<usize> =
+				(0..nb_parties).filter(|p| participates(*p) && won[*p] > national[*p]).collect();
+			if over.is_empty() {
+				break national;
+			}
+			overhang.extend(over);
+		};
+
+		let mut remaining: Vec<usize> =
+			(0..nb_parties).map(|p| national[p].saturating_sub(won[p])).collect();
+		let mut capacity: Vec<usize> = districts.iter().map(|d| d.leveling_seats).collect();
+		let mut leveling_seats = vec![vec![0; nb_parties]; districts.len()];
+		let divisors: Vec<BigRational> = self
+			.rules
+			.divisors()
+			.divisors()
+			.take(total_seats + 1)
+			.map(|d| d.map(to_big))
+			.collect::<Result<_, _>>()?;
+		while remaining.iter().any(|r| *r > 0) {
+			// Assign the next leveling seat to the highest quotient of any party
+			// that still has leveling seats in any district that can take it.
+			let mut best: Option<(usize, usize, BigRational)> = None;
+			for (d, district) in districts.iter().enumerate() {
+				if self.rules != Rules::Danish && capacity[d] == 0 {
+					continue;
+				}
+				for p in (0..nb_parties).filter(|p| remaining[*p] > 0) {
+					let seats = district_seats[d][p] + leveling_seats[d][p];
+					let mut quotient = big(district.votes[p]) / &divisors[seats];
+					if self.rules == Rules::Norwegian && district_votes[d] > 0 {
+						quotient = quotient * big(district.seats) / big(district_votes[d]);
+					}
+					if best.as_ref().is_none_or(|b| quotient > b.2) {
+						best = Some((d, p, quotient));
+					}
+				}
+			}
+			let (d, p, _) = best.ok_or(AllocationError::Infeasible)?;
+			leveling_seats[d][p] += 1;
+			remaining[p] -= 1;
+			capacity[d] = capacity[d].saturating_sub(1);
+		}
+
+		let seats = (0..nb_parties)
+			.map(|p| won[p] + leveling_seats.iter().map(|s| s[p]).sum::<usize>())
+			.collect();
+		Ok(CompensationResult {
+			district_seats,
+			leveling_seats,
+			seats,
+			qualified,
+			overhang,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn districts() -> Vec<District> {
+		vec![
+			District {
+				seats: 4,
+				leveling_seats: 1,
+				votes: vec![5000, 3000, 1500, 500],
+			},
+			District {
+				seats: 3,
+				leveling_seats: 1,
+				votes: vec![2000, 3500, 2500, 300],
+			},
+			District {
+				seats: 2,
+				leveling_seats: 1,
+				votes: vec![1000, 1200, 2000, 100],
+			},
+		]
+	}
+
+	#[test]
+	fn rules() {
+		let dhondt = HighestAverages::new(Method::DHondt);
+		let norwegian = Compensation::new(dhondt, Rules::Norwegian).allocate(&districts()).unwrap();
+		assert_eq!(
+			vec![vec![3, 1, 0, 0], vec![1, 1, 1, 0], vec![0, 1, 1, 0]],
+			norwegian.district_seats
+		);
+		assert_eq!(vec![5, 4, 3, 0], norwegian.seats);
+		assert_eq!(vec![true, true, true, false], norwegian.qualified);
+		assert_eq!(
+			vec![vec![0, 0, 1, 0], vec![0, 1, 0, 0], vec![1, 0, 0, 0]],
+			norwegian.leveling_seats
+		);
+
+		let swedish = Compensation::new(dhondt, Rules::Swedish).allocate(&districts()).unwrap();
+		assert_eq!(vec![5, 4, 3, 0], swedish.seats);
+
+		let danish = Compensation::new(dhondt, Rules::Danish)
+			.with_threshold(Rational::new(1, 100))
+			.allocate(&districts())
+			.unwrap();
+		assert_eq!(vec![true; 4], danish.qualified);
+		assert_eq!(vec![4, 4, 3, 1], danish.seats);
+		// The Danish rules don't limit the leveling seats per district.
+		assert_eq!(
+			vec![vec![0, 0, 1, 1], vec![0, 1, 0, 0], vec![0, 0, 0, 0]],
+			danish.leveling_seats
+		);
+	}
+
+	#[test]
+	fn overhang() {
+		let district = |leveling_seats| District {
+			seats: 1,
+			leveling_seats,
+			votes: vec![400, 350, 250],
+		};
+		let dhondt = HighestAverages::new(Method::DHondt);
+		let compensation = Compensation::new(dhondt, Rules::Swedish);
+		let result = compensation.allocate(&[district(1), district(0)]).unwrap();
+		assert_eq!(vec![0], result.overhang);
+		assert_eq!(vec![vec![0, 1, 0], vec![0, 0, 0]], result.leveling_seats);
+		assert_eq!(vec![2, 1, 0], result.seats);
+
+		let mut short = district(0);
+		short.votes.pop();
+		let result = compensation.allocate(&[district(1), short]);
+		assert_eq!(Err(AllocationError::MismatchedVotes), result);
+		assert_eq!(Err(AllocationError::NoParties), compensation.allocate(&[]));
+
+		let mut huge = district(0);
+		huge.votes = vec![usize::MAX / 2, 1, 1];
+		let result = compensation.allocate(&[huge.clone(), huge]);
+		assert_eq!(Err(AllocationError::Overflow), result);
+	}
+}
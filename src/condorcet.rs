@@ -0,0 +1,472 @@
+use std::cmp::Reverse;
+use std::error::Error;
+use std::fmt;
+
+use ballot::Profile;
+
+/// How the strength of a pairwise defeat is measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strength {
+	/// The number of voters that prefer the winner over the loser.
+	WinningVotes,
+	/// The difference between the voters that prefer the winner and the
+	/// voters that prefer the loser.
+	Margins,
+}
+
+/// The reasons a count can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CondorcetError {
+	/// There are no candidates.
+	NoCandidates,
+}
+
+impl fmt::Display for CondorcetError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			CondorcetError::NoCandidates => write!(f, "no candidates"),
+		}
+	}
+}
+
+impl Error for CondorcetError {}
+
+/// The pairwise comparison of all candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix {
+	/// The number of voters that prefer the row candidate over the column
+	/// candidate.
+	pub preferred: Vec<Vec<usize>>,
+}
+
+impl Matrix {
+	/// Count the pairwise preferences of all ballots in the profile.
+	pub fn new(profile: &Profile) -> Matrix {
+		let n = profile.nb_candidates();
+		let mut preferred = vec![vec![0; n]; n];
+		for ballot in profile.ballots().iter() {
+			let ranks: Vec<Option<usize>> = (0..n).map(|c| ballot.rank_of(c)).collect();
+			for a in 0..n {
+				for b in 0..n {
+					let prefers = match (ranks[a], ranks[b]) {
+						(Some(ra), Some(rb)) => ra < rb,
+						(Some(_), None) => true,
+						(None, _) => false,
+					};
+					if prefers {
+						preferred[a][b] += ballot.weight;
+					}
+				}
+			}
+		}
+		Matrix {
+			preferred,
+		}
+	}
+
+	/// The number of candidates.
+	pub fn nb_candidates(&self) -> usize {
+		self.preferred.len()
+	}
+
+	/// Whether a majority of the voters with a preference prefer a over b.
+	pub fn beats(&self, a: usize, b: usize) -> bool {
+		self.preferred[a][b] > self.preferred[b][a]
+	}
+
+	/// The candidate that beats all others, if any.
+	pub fn condorcet_winner(&self) -> Option<usize> {
+		let n = self.nb_candidates();
+		(0..n).find(|a| (0..n).all(|b| *a == b || self.beats(*a, b)))
+	}
+
+	/// The strength of the defeat of b by a, or zero if a doesn't beat b.
+	pub fn defeat(&self, a: usize, b: usize, strength: Strength) -> usize {
+		if !self.beats(a, b) {
+			return 0;
+		}
+		match strength {
+			Strength::WinningVotes => self.preferred[a][b],
+			Strength::Margins => self.preferred[a][b] - self.preferred[b][a],
+		}
+	}
+
+	/// All pairwise defeats, strongest first. Defeats of equal strength are
+	/// ordered by fewest opposing votes, then by candidate index.
+	pub fn defeats(&self, strength: Strength) -> Vec<Defeat> {
+		let n = self.nb_candidates();
+		let mut defeats = Vec::new();
+		for winner in 0..n {
+			for loser in 0..n {
+				if self.beats(winner, loser) {
+					defeats.push(Defeat {
+						winner,
+						loser,
+						strength: self.defeat(winner, loser, strength),
+					});
+				}
+			}
+		}
+		defeats.sort_by_key(|d| (Reverse(d.strength), self.preferred[d.loser][d.winner]));
+		defeats
+	}
+}
+
+/// A pairwise defeat of one candidate by another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Defeat {
+	pub winner: usize,
+	pub loser: usize,
+	pub strength: usize,
+}
+
+/// The result of the Schulze method.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SchulzeResult {
+	pub matrix: Matrix,
+	/// The strength of the strongest path from the row to the column
+	/// candidate.
+	pub paths: Vec<Vec<usize>>,
+	/// The full ranking from winners to losers, with equally ranked
+	/// candidates grouped together.
+	pub ranking: Vec<Vec<usize>>,
+	// The next candidate on the strongest path from the row to the column
+	// candidate.
+	next: Vec<Vec<usize>>,
+}
+
+impl SchulzeResult {
+	/// The winners, the candidates that no other candidate beats, more than
+	/// one if they are tied.
+	pub fn winners(&self) -> &[usize] {
+		&self.ranking[0]
+	}
+
+	/// The candidates on a strongest path from a to b, or None if there is
+	/// no path.
+	pub fn path(&self, a: usize, b: usize) -> Option<Vec<usize>> {
+		if a == b || self.paths[a][b] == 0 {
+			return None;
+		}
+		let mut path = vec![a];
+		let mut c = a;
+		while c != b {
+			c = self.next[c][b];
+			path.push(c);
+		}
+		Some(path)
+	}
+}
+
+/// Implements the Schulze beatpath method.
+/// For more info: https://en.wikipedia.org/wiki/Schulze_method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Schulze {
+	strength: Strength,
+}
+
+impl Schulze {
+	pub fn new(strength: Strength) -> Schulze {
+		Schulze {
+			strength,
+		}
+	}
+
+	/// Rank the candidates by the strength of their strongest paths.
+	pub fn count(&self, profile: &Profile) -> Result<SchulzeResult, CondorcetError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(CondorcetError::NoCandidates);
+		}
+		let matrix = Matrix::new(profile);
+
+		// Widest paths using the Floyd-Warshall algorithm.
+		let mut paths = vec![vec![0; n]; n];
+		let mut next = vec![vec![0; n]; n];
+		for a in 0..n {
+			for b in 0..n {
+				paths[a][b] = matrix.defeat(a, b, self.strength);
+				next[a][b] = b;
+			}
+		}
+		for k in 0..n {
+			for a in 0..n {
+				for b in 0..n {
+					if a == k || b == k || a == b {
+						continue;
+					}
+					let via = paths[a][k].min(paths[k][b]);
+					if via > paths[a][b] {
+						paths[a][b] = via;
+						next[a][b] = next[a][k];
+					}
+				}
+			}
+		}
+
+		// Rank the candidates that are not beaten by any other remaining
+		// candidate first. The relation is transitive, so there always are.
+		let mut remaining: Vec<usize> = (0..n).collect();
+		let mut ranking: Vec<Vec<usize>> = Vec::new();
+		while !remaining.is_empty() {
+			let unbeaten: Vec<usize> = remaining
+				.iter()
+				.cloned()
+				.filter(|a| remaining.iter().all(|b| paths[*a][*b] >= paths[*b][*a]))
+				.collect();
+			remaining.retain(|a| !unbeaten.contains(a));
+			ranking.push(unbeaten);
+		}
+
+		Ok(SchulzeResult {
+			matrix,
+			paths,
+			ranking,
+			next,
+		})
+	}
+}
+
+/// A step of the Ranked Pairs method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Lock {
+	pub defeat: Defeat,
+	/// Whether the defeat was locked in, or skipped because it would create a
+	/// cycle.
+	pub locked: bool,
+}
+
+/// The result of the Ranked Pairs method.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RankedPairsResult {
+	pub matrix: Matrix,
+	/// All defeats from strongest to weakest.
+	pub steps: Vec<Lock>,
+	/// The candidates that are not beaten by a locked defeat.
+	pub winners: Vec<usize>,
+}
+
+/// Implements Tideman's Ranked Pairs method.
+/// For more info: https://en.wikipedia.org/wiki/Ranked_pairs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RankedPairs {
+	strength: Strength,
+}
+
+impl RankedPairs {
+	pub fn new(strength: Strength) -> RankedPairs {
+		RankedPairs {
+			strength,
+		}
+	}
+
+	/// Lock in the defeats from strongest to weakest, skipping cycles.
+	pub fn count(&self, profile: &Profile) -> Result<RankedPairsResult, CondorcetError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(CondorcetError::NoCandidates);
+		}
+		let matrix = Matrix::new(profile);
+
+		let mut locked = vec![vec![false; n]; n];
+		let mut steps = Vec::new();
+		for defeat in matrix.defeats(self.strength) {
+			let lock = !reaches(&locked, defeat.loser, defeat.winner);
+			if lock {
+				locked[defeat.winner][defeat.loser] = true;
+			}
+			steps.push(Lock {
+				defeat,
+				locked: lock,
+			});
+		}
+		let winners = (0..n).filter(|b| (0..n).all(|a| !locked[a][*b])).collect();
+
+		Ok(RankedPairsResult {
+			matrix,
+			steps,
+			winners,
+		})
+	}
+}
+
+/// Whether there is a path from a to b in the graph.
+fn reaches(graph: &[Vec<bool>], a: usize, b: usize) -> bool {
+	let mut visited = vec![false; graph.len()];
+	let mut stack = vec![a];
+	while let Some(c) = stack.pop() {
+		if c == b {
+			return true;
+		}
+		if visited[c] {
+			continue;
+		}
+		visited[c] = true;
+		stack.extend((0..graph.len()).filter(|d| graph[c][*d]));
+	}
+	false
+}
+
+/// The result of the Minimax method.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MinimaxResult {
+	pub matrix: Matrix,
+	/// The strongest defeat of every candidate, None if they are unbeaten.
+	pub worst: Vec<Option<Defeat>>,
+	/// The candidates with the weakest worst defeat.
+	pub winners: Vec<usize>,
+}
+
+/// Implements the Minimax Condorcet method, electing the candidate whose
+/// strongest defeat is the weakest.
+/// For more info: https://en.wikipedia.org/wiki/Minimax_Condorcet_method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Minimax {
+	strength: Strength,
+}
+
+impl Minimax {
+	pub fn new(strength: Strength) -> Minimax {
+		Minimax {
+			strength,
+		}
+	}
+
+	/// Find the candidates with the weakest strongest defeat.
+	pub fn count(&self, profile: &Profile) -> Result<MinimaxResult, CondorcetError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(CondorcetError::NoCandidates);
+		}
+		let matrix = Matrix::new(profile);
+
+		let worst: Vec<Option<Defeat>> = (0..n)
+			.map(|loser| {
+				(0..n)
+					.filter(|winner| matrix.beats(*winner, loser))
+					.map(|winner| Defeat {
+						winner,
+						loser,
+						strength: matrix.defeat(winner, loser, self.strength),
+					})
+					.max_by_key(|d| (d.strength, Reverse(d.winner)))
+			})
+			.collect();
+		let score = |c: usize| worst[c].map_or(0, |d| d.strength);
+		let min = (0..n).map(score).min().unwrap();
+		let winners = (0..n).filter(|c| score(*c) == min).collect();
+
+		Ok(MinimaxResult {
+			matrix,
+			worst,
+			winners,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::profile;
+	use ballot::Ballot;
+
+	#[test]
+	fn matrix() {
+		// Memphis, Nashville, Chattanooga, Knoxville.
+		let ballots = vec![
+			Ballot::new(42, vec![0, 1, 2, 3]),
+			Ballot::new(26, vec![1, 2, 3, 0]),
+			Ballot::new(15, vec![2, 3, 1, 0]),
+			Ballot::new(17, vec![3, 2, 1, 0]),
+		];
+		let matrix = Matrix::new(&profile(4, ballots));
+		assert_eq!(
+			vec![
+				vec![0, 42, 42, 42],
+				vec![58, 0, 68, 68],
+				vec![58, 32, 0, 83],
+				vec![58, 32, 17, 0],
+			],
+			matrix.preferred
+		);
+		assert_eq!(Some(1), matrix.condorcet_winner());
+
+		let ballots = vec![Ballot::with_ranks(3, vec![vec![0, 1]]), Ballot::new(1, vec![2])];
+		let matrix = Matrix::new(&profile(3, ballots));
+		assert_eq!(vec![vec![0, 0, 3], vec![0, 0, 3], vec![1, 1, 0]], matrix.preferred);
+		assert_eq!(None, matrix.condorcet_winner());
+	}
+
+	#[test]
+	fn example_schulze() {
+		// A, B, C, D, E.
+		let ballots = vec![
+			Ballot::new(5, vec![0, 2, 1, 4, 3]),
+			Ballot::new(5, vec![0, 3, 4, 2, 1]),
+			Ballot::new(8, vec![1, 4, 3, 0, 2]),
+			Ballot::new(3, vec![2, 0, 1, 4, 3]),
+			Ballot::new(7, vec![2, 0, 4, 1, 3]),
+			Ballot::new(2, vec![2, 1, 0, 3, 4]),
+			Ballot::new(7, vec![3, 2, 4, 1, 0]),
+			Ballot::new(8, vec![4, 1, 0, 3, 2]),
+		];
+		let result = Schulze::new(Strength::WinningVotes).count(&profile(5, ballots)).unwrap();
+		assert_eq!(None, result.matrix.condorcet_winner());
+		assert_eq!(vec![vec![4], vec![0], vec![2], vec![1], vec![3]], result.ranking);
+		assert_eq!(&[4], result.winners());
+		assert_eq!(vec![25, 28, 28, 31, 0], result.paths[4]);
+		assert_eq!(Some(vec![4, 1, 0]), result.path(4, 0));
+		assert_eq!(None, result.path(0, 0));
+	}
+
+	#[test]
+	fn schulze_unbeaten() {
+		// B neither beats nor is beaten by anyone, so it ties with A.
+		let ballots = vec![Ballot::new(1, vec![0, 2, 1]), Ballot::new(1, vec![1, 0, 2])];
+		let result = Schulze::new(Strength::WinningVotes).count(&profile(3, ballots)).unwrap();
+		assert_eq!(&[0, 1], result.winners());
+		assert_eq!(vec![vec![0, 1], vec![2]], result.ranking);
+	}
+
+	#[test]
+	fn ranked_pairs() {
+		let ballots = vec![
+			Ballot::new(40, vec![0, 1, 2]),
+			Ballot::new(35, vec![1, 2, 0]),
+			Ballot::new(25, vec![2, 0, 1]),
+		];
+		let result = RankedPairs::new(Strength::Margins).count(&profile(3, ballots)).unwrap();
+		let steps: Vec<(usize, usize, usize, bool)> = result
+			.steps
+			.iter()
+			.map(|s| (s.defeat.winner, s.defeat.loser, s.defeat.strength, s.locked))
+			.collect();
+		assert_eq!(vec![(1, 2, 50, true), (0, 1, 30, true), (2, 0, 20, false)], steps);
+		assert_eq!(vec![0], result.winners);
+	}
+
+	#[test]
+	fn minimax() {
+		let ballots = vec![
+			Ballot::new(2, vec![0, 1, 2]),
+			Ballot::new(6, vec![1, 2, 0]),
+			Ballot::new(3, vec![2, 0, 1]),
+			Ballot::new(3, vec![2, 1, 0]),
+			Ballot::new(7, vec![0]),
+			Ballot::new(1, vec![1]),
+		];
+		let profile = profile(3, ballots);
+		let result = Minimax::new(Strength::WinningVotes).count(&profile).unwrap();
+		assert_eq!(vec![2], result.winners);
+		let worst: Vec<usize> = result.worst.iter().map(|d| d.unwrap().strength).collect();
+		assert_eq!(vec![12, 12, 9], worst);
+		let result = Minimax::new(Strength::Margins).count(&profile).unwrap();
+		assert_eq!(vec![1], result.winners);
+
+		let profile = Profile::new(Vec::new());
+		assert_eq!(
+			Err(CondorcetError::NoCandidates),
+			Minimax::new(Strength::Margins).count(&profile)
+		);
+	}
+}
@@ -0,0 +1,201 @@
+use num_rational::{BigRational, Rational};
+use num_traits::pow;
+
+use super::{big, to_big, AllocateSeats, AllocationError};
+use bounds::{BoundedAllocation, Bounds};
+use highest_averages::{HighestAverages, Method};
+
+/// Implements base-plus-proportional allocations for degressive
+/// proportionality: every state gets the same base seats, and the remaining
+/// seats are allocated by a divisor method on the populations, raised to a
+/// power, without exceeding the cap.
+/// For more info: https://en.wikipedia.org/wiki/Degressive_proportionality
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Compromise {
+	method: HighestAverages,
+	base: usize,
+	cap: Option<usize>,
+	power: Rational,
+}
+
+impl Compromise {
+	/// The Cambridge Compromise, which allocates the remaining seats by Adams,
+	/// rounding up. It was proposed with 5 base seats and a cap of 96 for the
+	/// European Parliament.
+	pub fn cambridge(base: usize, cap: Option<usize>) -> Compromise {
+		Compromise {
+			method: HighestAverages::new(Method::Adams),
+			base,
+			cap,
+			power: Rational::from_integer(1),
+		}
+	}
+
+	/// The power compromise, which allocates the remaining seats by
+	/// Sainte-Laguë on the populations raised to the given power, between 0
+	/// and 1. The power is usually chosen such that the largest state just
+	/// gets the cap.
+	pub fn power(base: usize, cap: Option<usize>, power: Rational) -> Compromise {
+		Compromise {
+			method: HighestAverages::new(Method::SainteLague),
+			base,
+			cap,
+			power,
+		}
+	}
+
+	/// Set the divisor method used to allocate the remaining seats.
+	pub fn with_method(self, method: HighestAverages) -> Compromise {
+		Compromise {
+			method,
+			..self
+		}
+	}
+
+	/// Calculates the seats per state and reports which states got the base
+	/// seats only or the cap. A power that isn't positive fails with
+	/// [AllocationError::InvalidDivisors].
+	pub fn allocate(
+		&self,
+		nb_seats: usize,
+		populations: &[usize],
+	) -> Result<BoundedAllocation, AllocationError> {
+		let base = self.base.checked_mul(populations.len()).ok_or(AllocationError::Overflow)?;
+		let free = nb_seats.checked_sub(base).ok_or(AllocationError::Infeasible)?;
+		let (a, b) = (*self.power.numer(), *self.power.denom());
+		if a <= 0 {
+			return Err(AllocationError::InvalidDivisors);
+		}
+
+		// With the power a/b, the quotient divisor / population^(a/b) is raised
+		// to the power b to compare it exactly.
+		let e = if self.method.method.is_squared() {
+			2
+		} else {
+			1
+		};
+		let weights: Vec<BigRational> =
+			populations.iter().map(|p| pow(big(*p), a as usize * e)).collect();
+		let quotient = |p: usize, d: Rational| Ok(pow(to_big(d), b as usize) / &weights[p]);
+		let max = self.cap.map(|c| c.saturating_sub(self.base));
+		let bounds = Bounds::uniform(populations.len(), 0, max);
+		let mut allocation =
+			self.method.allocate_bounded_by(free, populations, &bounds, quotient)?;
+		for seats in allocation.seats.iter_mut() {
+			*seats += self.base;
+		}
+		Ok(allocation)
+	}
+}
+
+impl AllocateSeats for Compromise {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		self.allocate(nb_seats, parties).map(|a| a.seats)
+	}
+}
+
+/// A violation of degressive proportionality between two states.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Violation {
+	/// The more populous state got fewer seats.
+	Seats {
+		larger: usize,
+		smaller: usize,
+	},
+	/// The more populous state got more seats per inhabitant.
+	Ratio {
+		larger: usize,
+		smaller: usize,
+	},
+}
+
+/// Check whether the allocation is degressively proportional: a more populous
+/// state must get at least as many seats as a less populous state, and at
+/// most as many seats per inhabitant. Returns the violations for every pair
+/// of states.
+/// Panics if there isn't a number of seats for every state.
+pub fn check_degressive(populations: &[usize], seats: &[usize]) -> Vec<Violation> {
+	assert_eq!(populations.len(), seats.len());
+	let mut violations = Vec::new();
+	for larger in 0..populations.len() {
+		for smaller in 0..populations.len() {
+			if populations[larger] <= populations[smaller] {
+				continue;
+			}
+			// Compare the inhabitants per seat without dividing by zero.
+			let (p_l, p_s) = (populations[larger] as u128, populations[smaller] as u128);
+			let (s_l, s_s) = (seats[larger] as u128, seats[smaller] as u128);
+			if s_l < s_s {
+				violations.push(Violation::Seats {
+					larger,
+					smaller,
+				});
+			} else if p_l * s_s < p_s * s_l {
+				violations.push(Violation::Ratio {
+					larger,
+					smaller,
+				});
+			}
+		}
+	}
+	violations
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bounds::Binding;
+
+	const POPULATIONS: [usize; 5] = [83000000, 38000000, 10500000, 2100000, 500000];
+
+	#[test]
+	fn cambridge() {
+		let cambridge = Compromise::cambridge(3, Some(40));
+		let allocation = cambridge.allocate(100, &POPULATIONS).unwrap();
+		assert_eq!(vec![40, 38, 13, 5, 4], allocation.seats);
+		assert_eq!(Some(Binding::Max), allocation.binding[0]);
+		assert!(check_degressive(&POPULATIONS, &allocation.seats).is_empty());
+		assert_eq!(
+			Err(AllocationError::Infeasible),
+			cambridge.try_allocate_seats(10, &POPULATIONS)
+		);
+	}
+
+	#[test]
+	fn power() {
+		let power = Compromise::power(3, Some(40), Rational::new(9, 10));
+		let seats = power.allocate_seats(100, POPULATIONS.to_vec());
+		assert_eq!(vec![40, 37, 14, 5, 4], seats);
+		assert!(check_degressive(&POPULATIONS, &seats).is_empty());
+
+		let invalid = Compromise::power(3, None, Rational::from_integer(0));
+		assert_eq!(
+			Err(AllocationError::InvalidDivisors),
+			invalid.try_allocate_seats(100, &POPULATIONS)
+		);
+		let huge = Compromise::cambridge(usize::MAX, None);
+		assert_eq!(Err(AllocationError::Overflow), huge.try_allocate_seats(100, &POPULATIONS));
+	}
+
+	#[test]
+	fn violations() {
+		let populations = [1000, 600, 100];
+		assert_eq!(
+			vec![
+				Violation::Seats {
+					larger: 0,
+					smaller: 1,
+				},
+				Violation::Ratio {
+					larger: 1,
+					smaller: 2,
+				},
+			],
+			check_degressive(&populations, &[5, 7, 1])
+		);
+	}
+}
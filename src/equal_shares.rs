@@ -0,0 +1,234 @@
+use std::cmp::Reverse;
+
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+
+use super::big;
+use approval::ApprovalError;
+use ballot::ApprovalProfile;
+use phragmen::{self, PhragmenResult};
+
+/// How the committee is completed when the budget runs out before all seats
+/// are filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Completion {
+	/// Leave the remaining seats empty.
+	None,
+	/// Fill the remaining seats with the candidates with the most approvals.
+	Utilitarian,
+	/// Fill the remaining seats with sequential Phragmén, where the voters
+	/// start with the amount they spent as their load.
+	Phragmen,
+	/// Increase the budget of the voters one seat at a time for as long as the
+	/// committee doesn't become too large, then fill any remaining seats with
+	/// the candidates with the most approvals.
+	Increment,
+}
+
+/// A candidate bought by their supporters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Purchase {
+	pub candidate: usize,
+	/// The most any voter paid for the candidate.
+	pub rho: BigRational,
+}
+
+/// The result of the Method of Equal Shares.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EqualSharesResult {
+	/// The elected candidates in the order they were elected.
+	pub winners: Vec<usize>,
+	/// The candidates bought by the voters, before completion.
+	pub purchases: Vec<Purchase>,
+	/// The budget left for every voter of each ballot.
+	pub budgets: Vec<BigRational>,
+}
+
+/// Implements the Method of Equal Shares, which gives every voter an equal
+/// share of the seats as a budget and buys the candidates that are cheapest
+/// for their supporters, who share the cost as equally as possible. Ties are
+/// broken in favour of the lowest candidate index.
+/// For more info: https://equalshares.net
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EqualShares {
+	completion: Completion,
+}
+
+impl EqualShares {
+	pub fn new(completion: Completion) -> EqualShares {
+		EqualShares {
+			completion,
+		}
+	}
+
+	/// Elect a committee of at most the given size.
+	pub fn elect(
+		&self,
+		nb_seats: usize,
+		profile: &ApprovalProfile,
+	) -> Result<EqualSharesResult, ApprovalError> {
+		if nb_seats > profile.nb_candidates() {
+			return Err(ApprovalError::NotEnoughCandidates);
+		}
+		let nb_voters = profile.nb_voters();
+		if nb_voters == 0 {
+			return Ok(EqualSharesResult {
+				winners: Vec::new(),
+				purchases: Vec::new(),
+				budgets: Vec::new(),
+			});
+		}
+
+		let costs = vec![BigRational::one(); profile.nb_candidates()];
+		let mut result = buy(profile, &costs, big(nb_seats) / big(nb_voters));
+		match self.completion {
+			Completion::None => {}
+			Completion::Utilitarian => utilitarian(nb_seats, profile, &mut result),
+			Completion::Phragmen => phragmen(nb_seats, profile, &mut result),
+			Completion::Increment => {
+				let nb_approved = profile.approvals().iter().filter(|a| **a > 0).count();
+				let mut total = nb_seats + 1;
+				while result.winners.len() < nb_seats.min(nb_approved) {
+					let next = buy(profile, &costs, big(total) / big(nb_voters));
+					if next.winners.len() > nb_seats {
+						break;
+					}
+					result = next;
+					total += 1;
+				}
+				utilitarian(nb_seats, profile, &mut result);
+			}
+		}
+		Ok(result)
+	}
+}
+
+/// Buy candidates at the given costs with the given budget per voter until no
+/// candidate is affordable. Every supporter values a candidate at its cost,
+/// so the candidate that is cheapest per unit of cost is bought, as in the
+/// default of Pabulib and equalshares.net.
+pub(crate) fn buy(
+	profile: &ApprovalProfile,
+	costs: &[BigRational],
+	budget: BigRational,
+) -> EqualSharesResult {
+	let ballots = profile.ballots();
+	let mut result = EqualSharesResult {
+		winners: Vec::new(),
+		purchases: Vec::new(),
+		budgets: vec![budget; ballots.len()],
+	};
+	loop {
+		let mut best: Option<Purchase> = None;
+		for c in (0..profile.nb_candidates()).filter(|c| !result.winners.contains(c)) {
+			let mut supporters: Vec<usize> = (0..ballots.len())
+				.filter(|i| ballots[*i].approves(c) && !result.budgets[*i].is_zero())
+				.collect();
+			supporters.sort_by(|a, b| result.budgets[*a].cmp(&result.budgets[*b]));
+
+			// Supporters with too little budget pay all they have, the others
+			// pay rho each.
+			let mut cost = costs[c].clone();
+			let mut weight: usize = supporters.iter().map(|i| ballots[*i].weight).sum();
+			for i in supporters {
+				let budget = &result.budgets[i];
+				if budget * big(weight) >= cost {
+					let rho = cost / big(weight);
+					// Compare rho per unit of cost by cross-multiplying.
+					let cheaper = |b: &Purchase| &rho * &costs[b.candidate] < &b.rho * &costs[c];
+					if best.as_ref().is_none_or(cheaper) {
+						best = Some(Purchase {
+							candidate: c,
+							rho,
+						});
+					}
+					break;
+				}
+				cost -= budget * big(ballots[i].weight);
+				weight -= ballots[i].weight;
+			}
+		}
+		let purchase = match best {
+			Some(purchase) => purchase,
+			None => return result,
+		};
+		for (ballot, budget) in ballots.iter().zip(result.budgets.iter_mut()) {
+			if ballot.approves(purchase.candidate) {
+				*budget = if *budget > purchase.rho {
+					&*budget - &purchase.rho
+				} else {
+					BigRational::zero()
+				};
+			}
+		}
+		result.winners.push(purchase.candidate);
+		result.purchases.push(purchase);
+	}
+}
+
+/// Fill the remaining seats with the candidates with the most approvals.
+fn utilitarian(nb_seats: usize, profile: &ApprovalProfile, result: &mut EqualSharesResult) {
+	let approvals = profile.approvals();
+	let mut candidates: Vec<usize> =
+		(0..profile.nb_candidates()).filter(|c| !result.winners.contains(c)).collect();
+	candidates.sort_by_key(|c| Reverse(approvals[*c]));
+	let missing = nb_seats.saturating_sub(result.winners.len());
+	result.winners.extend(candidates.into_iter().take(missing));
+}
+
+/// Fill the remaining seats with sequential Phragmén, starting from the amount
+/// the voters spent.
+fn phragmen(nb_seats: usize, profile: &ApprovalProfile, result: &mut EqualSharesResult) {
+	let budget = big(nb_seats) / big(profile.nb_voters());
+	let mut loads = PhragmenResult {
+		winners: result.winners.clone(),
+		loads: result.budgets.iter().map(|b| &budget - b).collect(),
+	};
+	let costs = vec![BigRational::one(); profile.nb_candidates()];
+	phragmen::extend(profile, &costs, &mut loads, |winners, _| winners.len() < nb_seats);
+	result.winners = loads.winners;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::approval_profile;
+
+	fn ratio(n: usize, d: usize) -> BigRational {
+		big(n) / big(d)
+	}
+
+	#[test]
+	fn completion() {
+		let profile = approval_profile(3, vec![(6, vec![0, 1]), (4, vec![2])]);
+		let result = EqualShares::new(Completion::None).elect(2, &profile).unwrap();
+		assert_eq!(vec![0], result.winners);
+		assert_eq!(
+			vec![Purchase {
+				candidate: 0,
+				rho: ratio(1, 6),
+			}],
+			result.purchases
+		);
+		assert_eq!(vec![ratio(1, 30), ratio(1, 5)], result.budgets);
+		let utilitarian = EqualShares::new(Completion::Utilitarian).elect(2, &profile).unwrap();
+		assert_eq!(vec![0, 1], utilitarian.winners);
+		let phragmen = EqualShares::new(Completion::Phragmen).elect(2, &profile).unwrap();
+		assert_eq!(vec![0, 2], phragmen.winners);
+		let increment = EqualShares::new(Completion::Increment).elect(2, &profile).unwrap();
+		assert_eq!(vec![0, 2], increment.winners);
+		assert_eq!(2, increment.purchases.len());
+	}
+
+	#[test]
+	fn unequal_budgets() {
+		// The voter approving both candidates can't pay an equal share of the
+		// second one, so the other supporter pays more.
+		let profile = approval_profile(3, vec![(1, vec![0, 1]), (1, vec![0]), (2, vec![1])]);
+		let result = EqualShares::new(Completion::None).elect(3, &profile).unwrap();
+		assert_eq!(vec![1, 0], result.winners);
+		assert_eq!(ratio(1, 3), result.purchases[0].rho);
+		assert_eq!(ratio(7, 12), result.purchases[1].rho);
+		assert_eq!(vec![ratio(0, 1), ratio(1, 6), ratio(5, 12)], result.budgets);
+	}
+}
@@ -1,37 +1,114 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
 use num_rational::Rational;
 
-use super::AllocateSeats;
+use super::{check_votes, to_isize, AllocateSeats, AllocationError};
+use bounds::{Binding, BoundedAllocation, Bounds};
+use tie::{Tie, TieBreak};
+
+/// A sequence of divisors given by the user. The divisors must not be
+/// negative, decrease or have a zero denominator, or the allocation fails
+/// with [AllocationError::InvalidDivisors].
+// Functions are compared by address, which is good enough to compare methods.
+#[allow(unknown_lints, unpredictable_function_pointer_comparisons)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sequence {
+	/// The given divisors as pairs of numerator and denominator. A party can't
+	/// get more seats than there are.
+	Given(&'static [(isize, isize)]),
+	/// The divisor for the seat with the given index, starting at 0.
+	Function(fn(usize) -> Rational),
+}
 
 /// The specific method used to specify the divisors.
-#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Method {
 	DHondt,
 	SainteLague,
+	/// The Belgian variant used for municipal elections, with divisors 1, 3/2,
+	/// 2, 5/2, ...
 	Imperiali,
+	/// Uses the geometric mean sqrt(n(n+1)) as divisor, so that every party
+	/// gets a first seat before any party gets a second one, as used for the
+	/// US House of Representatives.
 	HuntingtonHill,
 	Danish,
+	/// Uses n as divisor, rounding up, so that every party gets a first seat
+	/// before any party gets a second one.
+	Adams,
+	/// Uses the harmonic mean 2n(n+1)/(2n+1) as divisor.
+	Dean,
+	/// Sainte-Laguë with the given first divisor, such as 7/5 in Norway and
+	/// 6/5 in Sweden.
+	ModifiedSainteLague(Rational),
+	/// Uses the powers of two as divisors, as used in Macau.
+	Macanese,
+	/// Uses the divisors given by the user.
+	Custom(Sequence),
+}
+
+impl Method {
+	/// Whether the divisors of this method are squared to keep them rational.
+	/// The quotients are then compared as votes squared over the divisor.
+	pub(crate) fn is_squared(&self) -> bool {
+		*self == Method::HuntingtonHill
+	}
 }
 
 /// An implementation of an iterator that produces the divisors.
-struct Divisors {
-	method: Method,
+/// For Huntington-Hill, the divisors are irrational, so their squares are
+/// produced instead. A negative or decreasing divisor is an error.
+pub(crate) struct Divisors<'a> {
+	method: &'a Method,
 	idx: isize,
+	last: Rational,
 }
 
-impl Iterator for Divisors {
-	type Item = Rational;
+impl<'a> Iterator for Divisors<'a> {
+	type Item = Result<Rational, AllocationError>;
 
 	fn next(&mut self) -> Option<Self::Item> {
 		let i = self.idx;
-		let result: Rational = match self.method {
+		if let (0, Method::Custom(Sequence::Given(divisors))) = (i, self.method) {
+			// Check the whole sequence at once, so that it doesn't matter how
+			// many divisors are used.
+			if divisors.iter().any(|d| d.1 == 0) {
+				return Some(Err(AllocationError::InvalidDivisors));
+			}
+			let divisors: Vec<Rational> =
+				divisors.iter().map(|d| Rational::new(d.0, d.1)).collect();
+			let negative = divisors.first().is_some_and(|d| *d < Rational::from_integer(0));
+			if negative || divisors.windows(2).any(|w| w[1] < w[0]) {
+				return Some(Err(AllocationError::InvalidDivisors));
+			}
+		}
+		let result: Rational = match *self.method {
 			Method::DHondt => Rational::new(i + 1, 1),
 			Method::SainteLague => Rational::new(i * 2 + 1, 1),
 			Method::Imperiali => Rational::new(i + 2, 2),
-			Method::HuntingtonHill => Rational::new((i + 1) * (i + 2), 1) * -1,
+			Method::HuntingtonHill => Rational::new(i * (i + 1), 1),
 			Method::Danish => Rational::new(self.idx * 3 + 1, 1),
+			Method::Adams => Rational::new(i, 1),
+			Method::Dean => Rational::new(2 * i * (i + 1), 2 * i + 1),
+			Method::ModifiedSainteLague(first) if i == 0 => first,
+			Method::ModifiedSainteLague(_) => Rational::new(i * 2 + 1, 1),
+			Method::Macanese => match 2isize.checked_pow(i as u32) {
+				Some(d) => Rational::from_integer(d),
+				None => return Some(Err(AllocationError::Overflow)),
+			},
+			Method::Custom(Sequence::Given(divisors)) => {
+				let d = divisors.get(i as usize)?;
+				Rational::new(d.0, d.1)
+			}
+			Method::Custom(Sequence::Function(f)) => f(i as usize),
 		};
+		if result < self.last {
+			return Some(Err(AllocationError::InvalidDivisors));
+		}
 		self.idx += 1;
-		Some(result)
+		self.last = result;
+		Some(Ok(result))
 	}
 }
 
@@ -39,44 +116,136 @@ impl Iterator for Divisors {
 /// For more info: https://en.wikipedia.org/wiki/Highest_averages_method
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct HighestAverages {
-	method: Method,
+	pub(crate) method: Method,
+	tie_break: TieBreak,
+}
+
+/// The average of a party for a seat, its votes divided by the divisor.
+/// For Huntington-Hill, this is the square of the average.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Average {
+	Finite(Rational),
+	/// The average for a divisor of zero.
+	Infinite,
+}
+
+impl Average {
+	/// Convert a quotient as used to rank the seats into the average.
+	fn from_quotient(quotient: Rational) -> Average {
+		if quotient == Rational::from_integer(0) {
+			Average::Infinite
+		} else {
+			Average::Finite(quotient.recip())
+		}
+	}
+}
+
+/// A seat in the quotient table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Seat {
+	/// The index of the party.
+	pub party: usize,
+	/// The row in the table, i.e. the number of seats the party already had.
+	pub row: usize,
+	/// The average of the party for this seat.
+	pub average: Average,
+}
+
+/// The outcome of a seat allocation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Allocation {
+	/// The number of seats per party.
+	pub seats: Vec<usize>,
+	/// The seats in the order they were awarded.
+	pub awarded: Vec<Seat>,
+	/// The highest average that did not get a seat.
+	pub runner_up: Option<Seat>,
+	/// The divisors for each row of the quotient table.
+	pub divisors: Vec<Rational>,
+	/// The quotient table, the average of every party for every row.
+	pub averages: Vec<Vec<Average>>,
+	/// The number of votes each party would have needed in addition to win one
+	/// more seat, all other votes being equal. None if the party can't win
+	/// another seat from any other party.
+	pub margins: Vec<Option<usize>>,
+	/// The tie for the last seats, if there was one, and how it was broken.
+	pub tie: Option<Tie>,
 }
 
 impl HighestAverages {
 	pub fn new(method: Method) -> HighestAverages {
 		HighestAverages {
-			method: method,
+			method,
+			tie_break: TieBreak::default(),
+		}
+	}
+
+	/// Set the policy used to break a tie for the last seats.
+	/// The default is to favour the party listed first.
+	pub fn with_tie_break(self, tie_break: TieBreak) -> HighestAverages {
+		HighestAverages {
+			tie_break,
+			..self
 		}
 	}
 
 	/// Produce an iterator over the divisors.
-	fn divisors(&self) -> Divisors {
+	pub(crate) fn divisors(&self) -> Divisors<'_> {
 		Divisors {
-			method: self.method,
+			method: &self.method,
 			idx: 0,
+			last: Rational::from_integer(0),
 		}
 	}
-}
 
-impl AllocateSeats for HighestAverages {
-	fn allocate_seats(&self, nb_seats: usize, parties: Vec<usize>) -> Vec<usize> {
+	/// Calculate the quotient used to rank seats, which is the inverse of the
+	/// average, so that the lowest quotient gets the first seat.
+	fn quotient(&self, votes: usize, divisor: Rational) -> Result<Rational, AllocationError> {
+		let mut votes = to_isize(votes)?;
+		if self.method.is_squared() {
+			votes = votes.checked_mul(votes).ok_or(AllocationError::Overflow)?;
+		}
+		let denom = votes.checked_mul(*divisor.denom()).ok_or(AllocationError::Overflow)?;
+		Ok(Rational::new(*divisor.numer(), denom))
+	}
+
+	/// Calculates the seats per party and reports how a tie for the last seats
+	/// was broken. If the tie break policy is [TieBreak::Error], an unbroken
+	/// tie is returned as an error. Parties without votes get no seats.
+	/// This builds the full quotient table, so when only the seats are needed,
+	/// [AllocateSeats::try_allocate_seats] is faster.
+	pub fn allocate(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Allocation, AllocationError> {
+		check_votes(nb_seats, parties)?;
+
 		// Keep a sorted list of tuples (party_index, row, quotient).
 		let mut matrix = Vec::new();
+		let mut divisors = Vec::new();
 		for (row, divisor) in self.divisors().enumerate() {
+			let divisor = divisor?;
+			divisors.push(divisor);
 			// Add the new row to the matrix.
-			for (idx, votes) in parties.iter().enumerate() {
-				let quotient = Rational::new(1, *votes as isize) * divisor;
+			for (idx, votes) in parties.iter().enumerate().filter(|&(_, v)| *v > 0) {
+				let quotient = self.quotient(*votes, divisor)?;
 				matrix.push((idx, row, quotient));
 			}
 
-			// If the number of quotients is lower than the number of seats,
-			// we need another row.
-			if matrix.len() < nb_seats {
+			// Without seats to allocate, the first row will do.
+			if nb_seats == 0 {
+				break;
+			}
+
+			// If the number of quotients is not higher than the number of
+			// seats, we need another row to see if there is a tie.
+			if matrix.len() <= nb_seats {
 				continue;
 			}
 
 			// Sort by quotient so that the top items are the seat allocations.
-			matrix.sort_by(|e1, e2| e1.2.cmp(&e2.2));
+			matrix.sort_by_key(|e| e.2);
 
 			// If any allocated seat is from the last row, we need another row,
 			// otherwise we are finished.
@@ -84,26 +253,290 @@ impl AllocateSeats for HighestAverages {
 				break;
 			}
 		}
+		// A custom sequence may run out of divisors before all seats are
+		// allocated.
+		if matrix.len() < nb_seats {
+			return Err(AllocationError::Infeasible);
+		}
+		matrix.sort_by_key(|e| e.2);
+
+		let seat = |e: &(usize, usize, Rational)| Seat {
+			party: e.0,
+			row: e.1,
+			average: Average::from_quotient(e.2),
+		};
+		let mut awarded: Vec<Seat> = matrix[0..nb_seats].iter().map(&seat).collect();
+		let mut runner_up = matrix.get(nb_seats).map(&seat);
+		let mut tie = None;
+		if nb_seats > 0 {
+			// The quotients equal to the last allocated one are tied.
+			let last = matrix[nb_seats - 1].2;
+			let start = matrix.iter().position(|e| e.2 == last).unwrap();
+			let end = matrix.iter().rposition(|e| e.2 == last).unwrap() + 1;
+			if end > nb_seats {
+				let tied: Vec<usize> = matrix[start..end].iter().map(|e| e.0).collect();
+				let t = Tie::resolve(&self.tie_break, &tied, nb_seats - start, parties);
+				if !t.is_resolved() {
+					return Err(AllocationError::Tie(t));
+				}
+				// A party can be tied more than once if the divisors repeat, so
+				// every tied row is awarded at most once.
+				awarded.truncate(start);
+				let mut used = vec![false; end - start];
+				for party in t.winners.iter() {
+					let i = (0..end - start)
+						.find(|i| !used[*i] && matrix[start + i].0 == *party)
+						.unwrap();
+					used[i] = true;
+					awarded.push(seat(&matrix[start + i]));
+				}
+				runner_up = (0..end - start).find(|i| !used[*i]).map(|i| seat(&matrix[start + i]));
+				tie = Some(t);
+			}
+		}
 
 		let mut seats = vec![0; parties.len()];
-		for seat in matrix[0..nb_seats].iter() {
-			seats[seat.0] += 1;
+		for s in awarded.iter() {
+			seats[s.party] += 1;
+		}
+
+		let zero = Average::Finite(Rational::from_integer(0));
+		let mut averages = vec![vec![zero; parties.len()]; divisors.len()];
+		for e in matrix.iter() {
+			averages[e.1][e.0] = Average::from_quotient(e.2);
+		}
+
+		let mut margins = Vec::with_capacity(parties.len());
+		for (idx, votes) in parties.iter().enumerate() {
+			// To win another seat, the next quotient of the party must beat
+			// the last seat of any other party.
+			let last = awarded.iter().filter(|s| s.party != idx).map(|s| s.average).min();
+			let margin = match (last, self.divisors().nth(seats[idx]).transpose()?) {
+				(Some(Average::Finite(average)), Some(divisor)) => {
+					let needed = self.votes_to_beat(divisor, average.recip())?;
+					Some(needed.saturating_sub(*votes))
+				}
+				_ => None,
+			};
+			margins.push(margin);
+		}
+
+		Ok(Allocation {
+			seats,
+			awarded,
+			runner_up,
+			divisors,
+			averages,
+			margins,
+			tie,
+		})
+	}
+
+	/// Calculates the seats per party within the bounds, which is the same as
+	/// rounding the quotients of all parties for a common divisor and then
+	/// raising or lowering them to their bounds. A minimum is binding if the
+	/// party would not have won its last seat, a maximum if the party would
+	/// have won another seat. Parties without votes get their minimum.
+	/// Fails with [AllocationError::MismatchedVotes] if there are no bounds for
+	/// every party.
+	pub fn allocate_bounded(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+		bounds: &Bounds,
+	) -> Result<BoundedAllocation, AllocationError> {
+		self.allocate_bounded_by(nb_seats, parties, bounds, |p, d| self.quotient(parties[p], d))
+	}
+
+	/// Calculates the seats per party within the bounds like
+	/// [HighestAverages::allocate_bounded], ranking the seats by the given
+	/// quotient of a party and a divisor instead.
+	pub(crate) fn allocate_bounded_by<K, F>(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+		bounds: &Bounds,
+		quotient: F,
+	) -> Result<BoundedAllocation, AllocationError>
+	where
+		K: Ord + Clone,
+		F: Fn(usize, Rational) -> Result<K, AllocationError>,
+	{
+		bounds.check(nb_seats, parties.len())?;
+		let free = nb_seats - bounds.min.iter().sum::<usize>();
+		let (seats, last) = self.seats(free, parties, Some(bounds), &quotient)?;
+
+		let mut binding = vec![None; parties.len()];
+		for (p, votes) in parties.iter().enumerate() {
+			// Compare the average for the last or next seat of the party with
+			// the average of the last seat awarded above the minimums.
+			let at = |row: usize| match self.divisors().nth(row).transpose()? {
+				Some(divisor) if *votes > 0 => quotient(p, divisor).map(Some),
+				_ => Ok(None),
+			};
+			if seats[p] == bounds.min[p] && seats[p] > 0 {
+				let earned = match (at(seats[p] - 1)?, &last) {
+					(Some(q), Some(last)) => &q <= last,
+					_ => false,
+				};
+				if !earned {
+					binding[p] = Some(Binding::Min);
+				}
+			} else if Some(seats[p]) == bounds.max[p] {
+				if let (Some(q), Some(last)) = (at(seats[p])?, &last) {
+					if &q <= last {
+						binding[p] = Some(Binding::Max);
+					}
+				}
+			}
+		}
+		Ok(BoundedAllocation {
+			seats,
+			binding,
+		})
+	}
+
+	/// Calculates the seats per party like [HighestAverages::allocate], but
+	/// only keeps the next quotient of every party in a heap instead of the
+	/// full quotient table. With bounds, the parties start with their minimum
+	/// and the given number of seats is allocated on top of that. Also returns
+	/// the quotient of the last seat.
+	fn seats<K, F>(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+		bounds: Option<&Bounds>,
+		quotient: F,
+	) -> Result<(Vec<usize>, Option<K>), AllocationError>
+	where
+		K: Ord + Clone,
+		F: Fn(usize, Rational) -> Result<K, AllocationError>,
+	{
+		check_votes(nb_seats, parties)?;
+		let mut sequence = self.divisors();
+		let mut divisors: Vec<Rational> = Vec::new();
+		let mut divisor = |row: usize| -> Result<Option<Rational>, AllocationError> {
+			while divisors.len() <= row {
+				match sequence.next() {
+					Some(d) => divisors.push(d?),
+					None => return Ok(None),
+				}
+			}
+			Ok(Some(divisors[row]))
+		};
+		let min = |p: usize| bounds.map_or(0, |b| b.min[p]);
+		let below_max =
+			|p: usize, seats: usize| bounds.is_none_or(|b| b.max[p].is_none_or(|m| seats < m));
+
+		// The heap is ordered by quotient, then row, then party, like the
+		// sorted quotient table.
+		let mut heap = BinaryHeap::with_capacity(parties.len());
+		for idx in (0..parties.len()).filter(|p| parties[*p] > 0) {
+			if let (true, Some(d)) = (below_max(idx, min(idx)), divisor(min(idx))?) {
+				heap.push(Reverse((quotient(idx, d)?, min(idx), idx)));
+			}
+		}
+		let mut awarded: Vec<(K, usize, usize)> = Vec::with_capacity(nb_seats);
+		while awarded.len() < nb_seats {
+			let Reverse(seat) = heap.pop().ok_or(AllocationError::Infeasible)?;
+			if let (true, Some(next)) = (below_max(seat.2, seat.1 + 1), divisor(seat.1 + 1)?) {
+				heap.push(Reverse((quotient(seat.2, next)?, seat.1 + 1, seat.2)));
+			}
+			awarded.push(seat);
+		}
+
+		let mut seats: Vec<usize> = (0..parties.len()).map(min).collect();
+		let last = awarded.last().map(|e| e.0.clone());
+		if let Some(ref last) = last {
+			// The quotients equal to the last allocated one are tied.
+			let start = awarded.iter().position(|e| e.0 == *last).unwrap();
+			let mut tied: Vec<usize> = awarded[start..].iter().map(|e| e.2).collect();
+			while let Some(Reverse(seat)) = heap.pop() {
+				if seat.0 != *last {
+					break;
+				}
+				tied.push(seat.2);
+			}
+			if tied.len() > nb_seats - start {
+				let t = Tie::resolve(&self.tie_break, &tied, nb_seats - start, parties);
+				if !t.is_resolved() {
+					return Err(AllocationError::Tie(t));
+				}
+				awarded.truncate(start);
+				for party in t.winners {
+					seats[party] += 1;
+				}
+			}
+		}
+		for seat in awarded.iter() {
+			seats[seat.2] += 1;
+		}
+		Ok((seats, last))
+	}
+
+	/// Calculate the lowest number of votes for which the quotient for the
+	/// given divisor is lower than the given positive quotient.
+	fn votes_to_beat(
+		&self,
+		divisor: Rational,
+		quotient: Rational,
+	) -> Result<usize, AllocationError> {
+		// The quotient divisor / votes must be lower, so votes must be higher
+		// than divisor / quotient, or its square root for squared divisors.
+		let numer = divisor.numer().checked_mul(*quotient.denom());
+		let denom = divisor.denom().checked_mul(*quotient.numer());
+		let bound = match (numer, denom) {
+			(Some(n), Some(d)) => n / d,
+			_ => return Err(AllocationError::Overflow),
+		};
+		if self.method.is_squared() {
+			Ok(isqrt(bound) as usize + 1)
+		} else {
+			Ok(bound as usize + 1)
 		}
-		seats
+	}
+}
+
+/// Calculate the integer square root, rounded down.
+fn isqrt(n: isize) -> isize {
+	if n < 2 {
+		return n;
+	}
+	// Newton's method, starting from a value that is at least the root.
+	let mut x = n;
+	let mut y = (x + 1) / 2;
+	while y < x {
+		x = y;
+		y = (x + n / x) / 2;
+	}
+	x
+}
+
+impl AllocateSeats for HighestAverages {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		self.seats(nb_seats, parties, None, |p, d| self.quotient(parties[p], d)).map(|s| s.0)
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use bounds::Bounded;
 	use num_rational::Rational;
+	use tie::Lot;
 
 	fn take_n_divisors(method: Method, n: usize) -> Vec<Rational> {
 		Divisors {
-			method: method,
+			method: &method,
 			idx: 0,
+			last: Rational::from_integer(0),
 		}
 		.take(n)
+		.map(Result::unwrap)
 		.collect()
 	}
 
@@ -126,11 +559,156 @@ mod tests {
 			make_rationals(vec![(2, 2), (3, 2), (4, 2), (5, 2), (6, 2)]),
 			take_n_divisors(Method::Imperiali, 5)
 		);
-		//TODO(stevenroose) Huntington-Hill
+		assert_eq!(
+			make_rationals(vec![(0, 1), (2, 1), (6, 1), (12, 1), (20, 1)]),
+			take_n_divisors(Method::HuntingtonHill, 5)
+		);
 		assert_eq!(
 			make_rationals(vec![(1, 1), (4, 1), (7, 1), (10, 1), (13, 1)]),
 			take_n_divisors(Method::Danish, 5)
 		);
+		assert_eq!(
+			make_rationals(vec![(0, 1), (1, 1), (2, 1), (3, 1), (4, 1)]),
+			take_n_divisors(Method::Adams, 5)
+		);
+		assert_eq!(
+			make_rationals(vec![(0, 1), (4, 3), (12, 5), (24, 7), (40, 9)]),
+			take_n_divisors(Method::Dean, 5)
+		);
+		assert_eq!(
+			make_rationals(vec![(7, 5), (3, 1), (5, 1), (7, 1), (9, 1)]),
+			take_n_divisors(Method::ModifiedSainteLague(Rational::new(7, 5)), 5)
+		);
+		assert_eq!(
+			make_rationals(vec![(1, 1), (2, 1), (4, 1), (8, 1), (16, 1)]),
+			take_n_divisors(Method::Macanese, 5)
+		);
+	}
+
+	#[test]
+	fn custom_divisors() {
+		// Sainte-Laguë given as a function gives the same seats.
+		let sequence = Sequence::Function(|i| Rational::from_integer(2 * i as isize + 1));
+		let custom = HighestAverages::new(Method::Custom(sequence));
+		let sainte_lague = HighestAverages::new(Method::SainteLague);
+		let votes = vec![53000, 24000, 23000];
+		assert_eq!(sainte_lague.allocate_seats(7, votes.clone()), custom.allocate_seats(7, votes));
+
+		// Parties can't get more seats than there are divisors.
+		let given = Sequence::Given(&[(1, 1), (2, 1)]);
+		let custom = HighestAverages::new(Method::Custom(given));
+		assert_eq!(Ok(vec![2, 1]), custom.try_allocate_seats(3, &[900, 100]));
+		assert_eq!(Err(AllocationError::Infeasible), custom.try_allocate_seats(5, &[900, 100]));
+
+		// The divisors must not decrease or be negative.
+		let votes = [100, 90];
+		let invalid: [&'static [(isize, isize)]; 3] =
+			[&[(3, 1), (1, 1), (5, 1)], &[(-1, 1), (1, 1)], &[(1, 1), (2, 0)]];
+		for divisors in invalid.iter() {
+			let given = Sequence::Given(divisors);
+			let custom = HighestAverages::new(Method::Custom(given));
+			assert_eq!(
+				Err(AllocationError::InvalidDivisors),
+				custom.allocate(2, &votes).map(|_| ())
+			);
+			assert_eq!(Err(AllocationError::InvalidDivisors), custom.try_allocate_seats(2, &votes));
+		}
+		let sequence = Sequence::Function(|i| Rational::from_integer(10 - i as isize));
+		let custom = HighestAverages::new(Method::Custom(sequence));
+		assert_eq!(Err(AllocationError::InvalidDivisors), custom.try_allocate_seats(2, &votes));
+	}
+
+	#[test]
+	fn send_sync() {
+		fn check<T: Send + Sync>(_: &T) {}
+		let sequence = Sequence::Function(|i| Rational::from_integer(i as isize + 1));
+		check(&HighestAverages::new(Method::Custom(sequence)));
+	}
+
+	#[test]
+	fn repeated_divisors() {
+		// The second and third seats of the first party are tied with the
+		// first seat of the second party.
+		let custom =
+			HighestAverages::new(Method::Custom(Sequence::Given(&[(1, 1), (2, 1), (2, 1)])));
+		let allocation = custom.allocate(3, &[4, 2]).unwrap();
+		assert_eq!(vec![3, 0], allocation.seats);
+		let rows: Vec<(usize, usize)> =
+			allocation.awarded.iter().map(|s| (s.party, s.row)).collect();
+		assert_eq!(vec![(0, 0), (0, 1), (0, 2)], rows);
+		let runner_up = allocation.runner_up.unwrap();
+		assert_eq!((1, 0), (runner_up.party, runner_up.row));
+		assert_eq!(vec![0, 0, 1], allocation.tie.unwrap().parties);
+	}
+
+	#[test]
+	fn modified_sainte_lague() {
+		// The higher first divisor keeps the smallest party from its first seat.
+		let votes = vec![5000, 3000, 800];
+		let sainte_lague = HighestAverages::new(Method::SainteLague);
+		assert_eq!(vec![3, 2, 1], sainte_lague.allocate_seats(6, votes.clone()));
+		let modified = HighestAverages::new(Method::ModifiedSainteLague(Rational::new(7, 5)));
+		assert_eq!(vec![4, 2, 0], modified.allocate_seats(6, votes.clone()));
+		// Adams gives every party a first seat.
+		let adams = HighestAverages::new(Method::Adams);
+		assert_eq!(vec![3, 2, 1], adams.allocate_seats(6, votes));
+	}
+
+	#[test]
+	fn heap_same_as_table() {
+		// Few votes give many ties, which must be broken the same way.
+		let mut lot = Lot::new(2018);
+		let methods = vec![
+			Method::DHondt,
+			Method::SainteLague,
+			Method::Imperiali,
+			Method::HuntingtonHill,
+			Method::Danish,
+			Method::Adams,
+			Method::Dean,
+			Method::ModifiedSainteLague(Rational::new(7, 5)),
+			Method::Macanese,
+			Method::Custom(Sequence::Given(&[(1, 1), (2, 1), (2, 1)])),
+		];
+		let tie_breaks =
+			[TieBreak::PartyIndex, TieBreak::MostVotes, TieBreak::Lot(3), TieBreak::Error];
+		for _ in 0..500 {
+			let parties: Vec<usize> = (0..lot.below(8) + 1).map(|_| lot.below(50)).collect();
+			let nb_seats = lot.below(30);
+			let method = methods[lot.below(methods.len())];
+			let tie_break = tie_breaks[lot.below(tie_breaks.len())];
+			let allocator = HighestAverages::new(method).with_tie_break(tie_break);
+			assert_eq!(
+				allocator.allocate(nb_seats, &parties).map(|a| a.seats),
+				allocator.try_allocate_seats(nb_seats, &parties),
+				"{:?} {} {:?}",
+				allocator,
+				nb_seats,
+				parties
+			);
+		}
+	}
+
+	#[test]
+	fn bounds() {
+		let parties = [1000000, 30000, 21000, 100];
+		let allocator = HighestAverages::new(Method::DHondt);
+		assert_eq!(vec![10, 0, 0, 0], allocator.allocate_seats(10, parties.to_vec()));
+
+		let mut bounds = Bounds::uniform(4, 1, None);
+		bounds.max[0] = Some(6);
+		let allocation = allocator.allocate_bounded(10, &parties, &bounds).unwrap();
+		assert_eq!(vec![6, 2, 1, 1], allocation.seats);
+		// Party 2 would have won its seat anyway.
+		assert_eq!(vec![Some(Binding::Max), None, None, Some(Binding::Min)], allocation.binding);
+		let bounded = Bounded::new(allocator, bounds);
+		assert_eq!(Ok(allocation.seats), bounded.try_allocate_seats(10, &parties));
+
+		let bounds = Bounds::uniform(4, 0, Some(2));
+		assert_eq!(
+			Err(AllocationError::Infeasible),
+			allocator.allocate_bounded(10, &parties, &bounds)
+		);
 	}
 
 	#[test]
@@ -139,4 +717,129 @@ mod tests {
 		let seats = allocator.allocate_seats(13, vec![480, 310, 940, 270]);
 		assert_eq!(vec![3, 1, 8, 1], seats);
 	}
+
+	#[test]
+	fn huntington_hill_first_seat() {
+		let allocator = HighestAverages::new(Method::HuntingtonHill);
+		// Every state gets a seat before any state gets a second one.
+		assert_eq!(vec![2, 1, 1], allocator.allocate_seats(4, vec![1000000, 10, 1]));
+		// With fewer seats than states, the zero divisors are all tied.
+		let allocation = allocator.allocate(2, &[1000000, 10, 1]).unwrap();
+		assert_eq!(vec![1, 1, 0], allocation.seats);
+		assert_eq!(vec![0, 1, 2], allocation.tie.unwrap().parties);
+	}
+
+	#[test]
+	fn example_us_census2020() {
+		// Apportionment population per state, in alphabetical order.
+		let population = vec![
+			5030053, 736081, 7158923, 3013756, 39576757, 5782171, 3608298, 990837, 21570527,
+			10725274, 1460137, 1841377, 12822739, 6790280, 3192406, 2940865, 4509342, 4661468,
+			1363582, 6185278, 7033469, 10084442, 5709752, 2963914, 6160281, 1085407, 1963333,
+			3108462, 1379089, 9294493, 2120220, 20215751, 10453948, 779702, 11808848, 3963516,
+			4241500, 13011844, 1098163, 5124712, 887770, 6916897, 29183290, 3275252, 643503,
+			8654542, 7715946, 1795045, 5897473, 577719,
+		];
+		let expected = vec![
+			7, 1, 9, 4, 52, 8, 5, 1, 28, 14, 2, 2, 17, 9, 4, 4, 6, 6, 2, 8, 9, 13, 8, 4, 8, 2, 3,
+			4, 2, 12, 3, 26, 14, 1, 15, 5, 6, 17, 2, 7, 1, 9, 38, 4, 1, 11, 10, 2, 8, 1,
+		];
+		let allocator = HighestAverages::new(Method::HuntingtonHill);
+		let allocation = allocator.allocate(435, &population).unwrap();
+		assert_eq!(expected, allocation.seats);
+		assert_eq!(None, allocation.tie);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let allocator = HighestAverages::new(Method::DHondt);
+		assert_eq!(Err(AllocationError::NoParties), allocator.try_allocate_seats(5, &[]));
+		assert_eq!(Err(AllocationError::NoVotes), allocator.try_allocate_seats(5, &[0, 0]));
+		assert_eq!(Ok(vec![0, 0]), allocator.try_allocate_seats(0, &[0, 0]));
+		assert_eq!(Ok(vec![0, 3, 0]), allocator.try_allocate_seats(3, &[0, 10, 0]));
+		let huge = isize::MAX as usize + 1;
+		assert_eq!(Err(AllocationError::Overflow), allocator.try_allocate_seats(5, &[huge, 1]));
+		let allocator = HighestAverages::new(Method::HuntingtonHill);
+		let large = 1 << 40;
+		assert_eq!(Err(AllocationError::Overflow), allocator.try_allocate_seats(5, &[large, 1]));
+
+		// The Macanese divisors overflow after 63 seats for a party.
+		let allocator = HighestAverages::new(Method::Macanese);
+		assert_eq!(Ok(vec![10; 10]), allocator.try_allocate_seats(100, &[1; 10]));
+		let votes = [1000000000000, 1000000000000, 1, 1];
+		assert_eq!(Err(AllocationError::Overflow), allocator.try_allocate_seats(200, &votes));
+		assert_eq!(Err(AllocationError::Overflow), allocator.allocate(200, &votes).map(|_| ()));
+	}
+
+	#[test]
+	fn quotient_table() {
+		let allocator = HighestAverages::new(Method::DHondt);
+		let allocation = allocator.allocate(8, &[100000, 81000, 30000, 19000]).unwrap();
+		assert_eq!(vec![4, 3, 1, 0], allocation.seats);
+		let parties: Vec<usize> = allocation.awarded.iter().map(|s| s.party).collect();
+		assert_eq!(vec![0, 1, 0, 1, 0, 2, 1, 0], parties);
+		let average = |n| Average::Finite(Rational::from_integer(n));
+		assert_eq!(average(25000), allocation.awarded[7].average);
+		assert_eq!(
+			Some(Seat {
+				party: 1,
+				row: 3,
+				average: average(20250),
+			}),
+			allocation.runner_up
+		);
+		assert_eq!(
+			make_rationals(vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]),
+			allocation.divisors
+		);
+		assert_eq!(
+			vec![average(50000), average(40500), average(15000), average(9500)],
+			allocation.averages[1]
+		);
+		// Party 0 needs an average above 27000 for its fifth seat, the others
+		// need to beat the average of 25000 of the last seat of party 0.
+		assert_eq!(vec![Some(35001), Some(19001), Some(20001), Some(6001)], allocation.margins);
+	}
+
+	#[test]
+	fn huntington_hill_margins() {
+		let allocator = HighestAverages::new(Method::HuntingtonHill);
+		let allocation = allocator.allocate(3, &[1000, 10]).unwrap();
+		assert_eq!(vec![2, 1], allocation.seats);
+		assert_eq!(Average::Infinite, allocation.awarded[0].average);
+		// Party 1 needs a squared average above 1000 squared over 2 for its
+		// second seat, so more than 1000 votes.
+		assert_eq!(vec![None, Some(991)], allocation.margins);
+	}
+
+	#[test]
+	fn tie_breaks() {
+		// Both parties have a quotient of 300 for the last seat.
+		let votes = vec![600, 900, 100];
+		let allocator = HighestAverages::new(Method::DHondt);
+		let allocation = allocator.allocate(4, &votes).unwrap();
+		assert_eq!(vec![2, 2, 0], allocation.seats);
+		assert_eq!(1, allocation.runner_up.unwrap().party);
+		let tie = allocation.tie.unwrap();
+		assert_eq!(vec![0, 1], tie.parties);
+		assert_eq!(1, tie.seats);
+		assert_eq!(vec![0], tie.winners);
+
+		let allocator = allocator.with_tie_break(TieBreak::MostVotes);
+		assert_eq!(vec![1, 3, 0], allocator.allocate_seats(4, votes.clone()));
+
+		let allocator = allocator.with_tie_break(TieBreak::Error);
+		match allocator.allocate(4, &votes) {
+			Err(AllocationError::Tie(tie)) => {
+				assert_eq!(vec![0, 1], tie.parties);
+				assert!(tie.winners.is_empty());
+			}
+			r => panic!("expected a tie, got {:?}", r),
+		}
+
+		// No tie if the tied quotients all get a seat.
+		let allocation = allocator.allocate(5, &votes).unwrap();
+		assert_eq!(vec![2, 3, 0], allocation.seats);
+		assert_eq!(None, allocation.tie);
+	}
 }
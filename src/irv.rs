@@ -0,0 +1,453 @@
+use std::error::Error;
+use std::fmt;
+
+use ballot::{Ballot, Profile};
+use tie::Lot;
+
+/// How many candidates are eliminated in a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Elimination {
+	/// Only the candidate with the fewest votes is eliminated.
+	Single,
+	/// All candidates that can't win are eliminated together, i.e. the largest
+	/// group of lowest candidates whose combined votes are fewer than the votes
+	/// of the next candidate.
+	Batch,
+}
+
+/// How a tie for the fewest votes is broken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TieRule {
+	/// Eliminate the candidate with the fewest votes in the most recent
+	/// previous round in which the tied candidates had different votes.
+	Backward,
+	/// Eliminate the candidate with the fewest votes in the first round in
+	/// which the tied candidates had different votes.
+	Forward,
+	/// Draw lots using a pseudo-random generator with the given seed.
+	Lot(u64),
+}
+
+/// How a ranking with more than one candidate, an overvote, is handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Overvote {
+	/// The ballot becomes inactive when it reaches the overvote.
+	Exhaust,
+	/// The overvoted ranking is skipped.
+	Skip,
+}
+
+/// The number of ballots that no longer count for any candidate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Inactive {
+	/// Ballots without any continuing candidates ranked.
+	pub exhausted: usize,
+	/// Ballots that reached an overvote.
+	pub overvotes: usize,
+	/// Ballots with too many consecutive skipped rankings.
+	pub skipped: usize,
+}
+
+impl Inactive {
+	/// The total number of inactive ballots.
+	pub fn total(&self) -> usize {
+		self.exhausted + self.overvotes + self.skipped
+	}
+}
+
+/// A round of the count.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Round {
+	/// The votes of every candidate, zero for eliminated candidates.
+	pub tallies: Vec<usize>,
+	/// The ballots that don't count for any candidate.
+	pub inactive: Inactive,
+	/// The candidates eliminated at the end of this round.
+	pub eliminated: Vec<usize>,
+}
+
+/// The result of an instant-runoff count.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Count {
+	/// The winning candidate.
+	pub winner: usize,
+	/// All rounds of the count.
+	pub rounds: Vec<Round>,
+}
+
+/// The reasons a count can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IrvError {
+	/// There are no candidates.
+	NoCandidates,
+	/// None of the ballots count for any candidate.
+	NoVotes,
+	/// The weights of the ballots are too large to add up.
+	Overflow,
+}
+
+impl fmt::Display for IrvError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			IrvError::NoCandidates => write!(f, "no candidates"),
+			IrvError::NoVotes => write!(f, "no votes"),
+			IrvError::Overflow => write!(f, "arithmetic overflow"),
+		}
+	}
+}
+
+impl Error for IrvError {}
+
+/// Implements instant-runoff voting, electing a single candidate by majority
+/// of the continuing ballots.
+/// For more info: https://en.wikipedia.org/wiki/Instant-runoff_voting
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Irv {
+	elimination: Elimination,
+	tie_rule: TieRule,
+	overvote: Overvote,
+	max_skipped: Option<usize>,
+}
+
+impl Default for Irv {
+	fn default() -> Irv {
+		Irv::new()
+	}
+}
+
+impl Irv {
+	/// Create a count that eliminates one candidate per round, breaks ties
+	/// backward, exhausts ballots at an overvote and allows skipped rankings.
+	pub fn new() -> Irv {
+		Irv {
+			elimination: Elimination::Single,
+			tie_rule: TieRule::Backward,
+			overvote: Overvote::Exhaust,
+			max_skipped: None,
+		}
+	}
+
+	pub fn with_elimination(self, elimination: Elimination) -> Irv {
+		Irv {
+			elimination,
+			..self
+		}
+	}
+
+	pub fn with_tie_rule(self, tie_rule: TieRule) -> Irv {
+		Irv {
+			tie_rule,
+			..self
+		}
+	}
+
+	pub fn with_overvote(self, overvote: Overvote) -> Irv {
+		Irv {
+			overvote,
+			..self
+		}
+	}
+
+	/// Set the number of consecutive skipped rankings that are allowed; a
+	/// ballot with more becomes inactive when it reaches them. For example,
+	/// Maine allows a single skipped ranking.
+	pub fn with_max_skipped(self, max_skipped: Option<usize>) -> Irv {
+		Irv {
+			max_skipped,
+			..self
+		}
+	}
+
+	/// Count the ballots and find the winner.
+	pub fn count(&self, profile: &Profile) -> Result<Count, IrvError> {
+		let nb_candidates = profile.nb_candidates();
+		if nb_candidates == 0 {
+			return Err(IrvError::NoCandidates);
+		}
+		let mut lot = match self.tie_rule {
+			TieRule::Lot(seed) => Some(Lot::new(seed)),
+			_ => None,
+		};
+
+		let add = |total: &mut usize, weight: usize| -> Result<(), IrvError> {
+			*total = total.checked_add(weight).ok_or(IrvError::Overflow)?;
+			Ok(())
+		};
+		let mut continuing = vec![true; nb_candidates];
+		let mut rounds: Vec<Round> = Vec::new();
+		loop {
+			let mut round = Round {
+				tallies: vec![0; nb_candidates],
+				inactive: Inactive::default(),
+				eliminated: Vec::new(),
+			};
+			for ballot in profile.ballots().iter() {
+				let total = match self.top(ballot, &continuing) {
+					Top::Candidate(c) => &mut round.tallies[c],
+					Top::Exhausted => &mut round.inactive.exhausted,
+					Top::Overvote => &mut round.inactive.overvotes,
+					Top::Skipped => &mut round.inactive.skipped,
+				};
+				add(total, ballot.weight)?;
+			}
+			let mut active = 0;
+			for tally in round.tallies.iter() {
+				add(&mut active, *tally)?;
+			}
+			if active == 0 {
+				return Err(IrvError::NoVotes);
+			}
+
+			let mut remaining: Vec<usize> = (0..nb_candidates).filter(|c| continuing[*c]).collect();
+			remaining.sort_by_key(|c| round.tallies[*c]);
+			let top = *remaining.last().unwrap();
+			if round.tallies[top] > active - round.tallies[top] || remaining.len() == 1 {
+				rounds.push(round);
+				return Ok(Count {
+					winner: top,
+					rounds,
+				});
+			}
+
+			let eliminated = match self.elimination {
+				Elimination::Batch => batch(&remaining, &round.tallies),
+				Elimination::Single => Vec::new(),
+			};
+			round.eliminated = if eliminated.is_empty() {
+				// Eliminate the lowest candidate, breaking ties if needed.
+				let fewest = round.tallies[remaining[0]];
+				let tied: Vec<usize> =
+					remaining.iter().cloned().filter(|c| round.tallies[*c] == fewest).collect();
+				vec![self.break_tie(tied, &rounds, lot.as_mut())]
+			} else {
+				eliminated
+			};
+			for c in round.eliminated.iter() {
+				continuing[*c] = false;
+			}
+			rounds.push(round);
+		}
+	}
+
+	/// Find the highest ranked continuing candidate on the ballot.
+	fn top(&self, ballot: &Ballot, continuing: &[bool]) -> Top {
+		let mut skipped = 0;
+		for (i, rank) in ballot.ranks.iter().enumerate() {
+			if rank.is_empty() {
+				skipped += 1;
+				if self.max_skipped.is_some_and(|max| skipped > max) {
+					// Blank rankings at the end of the ballot just exhaust it.
+					if ballot.ranks[i + 1..].iter().all(|r| r.is_empty()) {
+						return Top::Exhausted;
+					}
+					return Top::Skipped;
+				}
+				continue;
+			}
+			skipped = 0;
+			if rank.len() > 1 && rank.iter().any(|c| *c != rank[0]) {
+				match self.overvote {
+					Overvote::Exhaust => return Top::Overvote,
+					Overvote::Skip => continue,
+				}
+			}
+			if continuing[rank[0]] {
+				return Top::Candidate(rank[0]);
+			}
+		}
+		Top::Exhausted
+	}
+
+	/// Pick the candidate to eliminate from the tied candidates.
+	fn break_tie(&self, mut tied: Vec<usize>, rounds: &[Round], lot: Option<&mut Lot>) -> usize {
+		if let Some(lot) = lot {
+			return tied[lot.below(tied.len())];
+		}
+		let history: Vec<&Round> = match self.tie_rule {
+			TieRule::Forward => rounds.iter().collect(),
+			_ => rounds.iter().rev().collect(),
+		};
+		for round in history {
+			let fewest = tied.iter().map(|c| round.tallies[*c]).min().unwrap();
+			tied.retain(|c| round.tallies[*c] == fewest);
+			if tied.len() == 1 {
+				break;
+			}
+		}
+		// Still tied, so eliminate the candidate listed last.
+		*tied.iter().max().unwrap()
+	}
+}
+
+/// The top choice of a ballot in a round.
+enum Top {
+	Candidate(usize),
+	Exhausted,
+	Overvote,
+	Skipped,
+}
+
+/// Find the largest group of lowest candidates that can be eliminated together
+/// because their combined votes are fewer than the votes of the next one. The
+/// candidates are sorted by increasing votes.
+fn batch(sorted: &[usize], tallies: &[usize]) -> Vec<usize> {
+	let mut size = 0;
+	let mut sum = 0;
+	for k in 1..sorted.len() {
+		sum += tallies[sorted[k - 1]];
+		if sum < tallies[sorted[k]] {
+			size = k;
+		}
+	}
+	sorted[0..size].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::profile;
+
+	#[test]
+	fn example_tennessee() {
+		// Memphis, Nashville, Chattanooga, Knoxville.
+		let ballots = vec![
+			Ballot::new(42, vec![0, 1, 2, 3]),
+			Ballot::new(26, vec![1, 2, 3, 0]),
+			Ballot::new(15, vec![2, 3, 1, 0]),
+			Ballot::new(17, vec![3, 2, 1, 0]),
+		];
+		let count = Irv::new().count(&profile(4, ballots)).unwrap();
+		assert_eq!(3, count.winner);
+		assert_eq!(3, count.rounds.len());
+		assert_eq!(vec![2], count.rounds[0].eliminated);
+		assert_eq!(vec![42, 26, 0, 32], count.rounds[1].tallies);
+		assert_eq!(vec![1], count.rounds[1].eliminated);
+		assert_eq!(vec![42, 0, 0, 58], count.rounds[2].tallies);
+	}
+
+	#[test]
+	fn batch_elimination() {
+		let ballots = vec![
+			Ballot::new(40, vec![0]),
+			Ballot::new(35, vec![1]),
+			Ballot::new(10, vec![2, 1]),
+			Ballot::new(8, vec![3, 1]),
+			Ballot::new(7, vec![4, 1]),
+		];
+		let batch = Irv::new().with_elimination(Elimination::Batch);
+		let count = batch.count(&profile(5, ballots.clone())).unwrap();
+		assert_eq!(1, count.winner);
+		assert_eq!(vec![4, 3, 2], count.rounds[0].eliminated);
+		assert_eq!(2, count.rounds.len());
+		let count = Irv::new().count(&profile(5, ballots)).unwrap();
+		assert_eq!(1, count.winner);
+		assert_eq!(4, count.rounds.len());
+	}
+
+	#[test]
+	fn inactive_ballots() {
+		let ballots = vec![
+			Ballot::new(9, vec![0]),
+			Ballot::new(8, vec![1]),
+			Ballot::new(2, vec![2]),
+			Ballot::with_ranks(2, vec![vec![2], vec![0, 1], vec![1]]),
+			Ballot::with_ranks(3, vec![vec![2], vec![], vec![], vec![1]]),
+			Ballot::with_ranks(1, vec![]),
+		];
+		let count = Irv::new().count(&profile(3, ballots.clone())).unwrap();
+		assert_eq!(1, count.winner);
+		let last = count.rounds.last().unwrap();
+		assert_eq!(vec![9, 11, 0], last.tallies);
+		assert_eq!(
+			Inactive {
+				exhausted: 3,
+				overvotes: 2,
+				skipped: 0,
+			},
+			last.inactive
+		);
+
+		let maine = Irv::new().with_max_skipped(Some(1)).with_overvote(Overvote::Skip);
+		let count = maine.count(&profile(3, ballots)).unwrap();
+		assert_eq!(1, count.winner);
+		let last = count.rounds.last().unwrap();
+		assert_eq!(vec![9, 10, 0], last.tallies);
+		assert_eq!(
+			Inactive {
+				exhausted: 3,
+				overvotes: 0,
+				skipped: 3,
+			},
+			last.inactive
+		);
+
+		// Blank rankings after the last choice exhaust the ballot.
+		let ballots = vec![
+			Ballot::new(3, vec![0]),
+			Ballot::new(2, vec![1]),
+			Ballot::with_ranks(1, vec![vec![2], vec![], vec![]]),
+			Ballot::with_ranks(1, vec![vec![2], vec![], vec![], vec![1]]),
+		];
+		let count = Irv::new().with_max_skipped(Some(1)).count(&profile(3, ballots)).unwrap();
+		assert_eq!(
+			Inactive {
+				exhausted: 1,
+				overvotes: 0,
+				skipped: 1,
+			},
+			count.rounds[1].inactive
+		);
+	}
+
+	#[test]
+	fn tie_rules() {
+		// Candidates 3 and 4 are tied in the first round. Without earlier
+		// rounds to look back on, the candidate listed last is eliminated.
+		let ballots = vec![
+			Ballot::new(10, vec![0]),
+			Ballot::new(3, vec![1]),
+			Ballot::new(4, vec![2]),
+			Ballot::new(2, vec![3, 1]),
+			Ballot::new(2, vec![4, 1]),
+		];
+		for tie_rule in [TieRule::Backward, TieRule::Forward].iter() {
+			let count = Irv::new().with_tie_rule(*tie_rule).count(&profile(5, ballots.clone()));
+			let count = count.unwrap();
+			assert_eq!(vec![4], count.rounds[0].eliminated);
+			assert_eq!(vec![3], count.rounds[1].eliminated);
+			assert_eq!(vec![10, 7, 4, 0, 0], count.rounds[2].tallies);
+		}
+
+		// Candidates 1 and 2 are tied in the third round.
+		let ballots = vec![
+			Ballot::new(10, vec![0]),
+			Ballot::new(4, vec![1]),
+			Ballot::new(5, vec![2]),
+			Ballot::new(2, vec![3, 1]),
+			Ballot::new(1, vec![4, 2]),
+			Ballot::new(2, vec![4]),
+		];
+		let backward = Irv::new().count(&profile(5, ballots.clone())).unwrap();
+		assert_eq!(vec![10, 6, 6, 0, 0], backward.rounds[2].tallies);
+		assert_eq!(vec![2], backward.rounds[2].eliminated);
+		let forward =
+			Irv::new().with_tie_rule(TieRule::Forward).count(&profile(5, ballots.clone()));
+		assert_eq!(vec![1], forward.unwrap().rounds[2].eliminated);
+		let lot = Irv::new().with_tie_rule(TieRule::Lot(1)).count(&profile(5, ballots.clone()));
+		let again = Irv::new().with_tie_rule(TieRule::Lot(1)).count(&profile(5, ballots));
+		assert_eq!(lot, again);
+	}
+
+	#[test]
+	fn large_weights() {
+		// Twice the winner's tally doesn't fit, but the majority is still found.
+		let ballots =
+			vec![Ballot::new(usize::MAX / 3 * 2, vec![0]), Ballot::new(usize::MAX / 4, vec![1])];
+		assert_eq!(0, Irv::new().count(&profile(2, ballots)).unwrap().winner);
+		let ballots = vec![
+			Ballot::new(usize::MAX / 2 + 1, vec![0]),
+			Ballot::new(usize::MAX / 2 + 1, vec![1]),
+		];
+		assert_eq!(Err(IrvError::Overflow), Irv::new().count(&profile(2, ballots)));
+	}
+}
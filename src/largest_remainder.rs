@@ -0,0 +1,156 @@
+use num_rational::Rational;
+
+use super::{check_votes, to_isize, AllocateSeats, AllocationError};
+
+/// The quota used to determine how many votes a seat is worth.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub enum Quota {
+	/// votes / seats
+	Hare,
+	/// floor(votes / (seats + 1)) + 1
+	Droop,
+	/// votes / (seats + 1)
+	HagenbachBischoff,
+	/// votes / (seats + 2)
+	Imperiali,
+}
+
+impl Quota {
+	/// Calculate the quota for the given total number of votes and seats.
+	/// The number of seats must not be zero.
+	fn value(&self, total_votes: usize, nb_seats: usize) -> Result<Rational, AllocationError> {
+		let votes = to_isize(total_votes)?;
+		let seats = to_isize(nb_seats)?;
+		let plus = |n: isize| seats.checked_add(n).ok_or(AllocationError::Overflow);
+		Ok(match *self {
+			Quota::Hare => Rational::new(votes, seats),
+			Quota::Droop => Rational::from_integer(votes / plus(1)? + 1),
+			Quota::HagenbachBischoff => Rational::new(votes, plus(1)?),
+			Quota::Imperiali => Rational::new(votes, plus(2)?),
+		})
+	}
+}
+
+/// Implements the largest remainder method or quota method for seat allocation.
+/// For more info: https://en.wikipedia.org/wiki/Largest_remainder_method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LargestRemainder {
+	quota: Quota,
+}
+
+impl LargestRemainder {
+	pub fn new(quota: Quota) -> LargestRemainder {
+		LargestRemainder {
+			quota,
+		}
+	}
+}
+
+impl AllocateSeats for LargestRemainder {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		check_votes(nb_seats, parties)?;
+		if nb_seats == 0 {
+			return Ok(vec![0; parties.len()]);
+		}
+		let total = parties.iter().try_fold(0usize, |sum, v| sum.checked_add(*v));
+		let quota = self.quota.value(total.ok_or(AllocationError::Overflow)?, nb_seats)?;
+
+		// Every full quota gives a seat, keep the remainders for the rest.
+		let mut seats = Vec::with_capacity(parties.len());
+		let mut remainders = Vec::with_capacity(parties.len());
+		for (idx, votes) in parties.iter().enumerate() {
+			let numer = to_isize(*votes)?.checked_mul(*quota.denom());
+			let quotient = Rational::new(numer.ok_or(AllocationError::Overflow)?, *quota.numer());
+			seats.push(quotient.to_integer() as usize);
+			remainders.push((idx, quotient.fract()));
+		}
+
+		let allocated: usize = seats.iter().sum();
+		if allocated <= nb_seats {
+			// Hand out the remaining seats by largest remainder, one per party
+			// with votes. The sort is stable, so equal remainders favour the
+			// first party. Quotas as large as Droop can leave more seats than
+			// parties with votes, those are handed out in further rounds in
+			// the same order.
+			remainders.retain(|r| parties[r.0] > 0);
+			remainders.sort_by_key(|r| ::std::cmp::Reverse(r.1));
+			for r in remainders.iter().cycle().take(nb_seats - allocated) {
+				seats[r.0] += 1;
+			}
+		} else {
+			// Quotas smaller than Hagenbach-Bischoff can allocate more seats
+			// than available, take them back from the smallest remainders, one
+			// per party with seats. Only if every such party lost a seat, the
+			// next round starts again from the smallest remainder.
+			remainders.retain(|r| seats[r.0] > 0);
+			remainders.sort_by_key(|r| r.1);
+			let mut excess = allocated - nb_seats;
+			while excess > 0 {
+				for r in remainders.iter() {
+					if excess > 0 && seats[r.0] > 0 {
+						seats[r.0] -= 1;
+						excess -= 1;
+					}
+				}
+			}
+		}
+		Ok(seats)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn example_votes() -> Vec<usize> {
+		vec![47000, 16000, 15800, 12000, 6100, 3100]
+	}
+
+	#[test]
+	fn quotas() {
+		assert_eq!(Ok(Rational::from_integer(10000)), Quota::Hare.value(100000, 10));
+		assert_eq!(Ok(Rational::from_integer(9091)), Quota::Droop.value(100000, 10));
+		assert_eq!(Ok(Rational::new(100000, 11)), Quota::HagenbachBischoff.value(100000, 10));
+		assert_eq!(Ok(Rational::new(100000, 12)), Quota::Imperiali.value(100000, 10));
+	}
+
+	#[test]
+	fn example_wikipedia() {
+		let hare = LargestRemainder::new(Quota::Hare);
+		assert_eq!(vec![5, 2, 1, 1, 1, 0], hare.allocate_seats(10, example_votes()));
+		let droop = LargestRemainder::new(Quota::Droop);
+		assert_eq!(vec![5, 2, 2, 1, 0, 0], droop.allocate_seats(10, example_votes()));
+		let hb = LargestRemainder::new(Quota::HagenbachBischoff);
+		assert_eq!(vec![5, 2, 2, 1, 0, 0], hb.allocate_seats(10, example_votes()));
+	}
+
+	#[test]
+	fn invalid_input() {
+		let allocator = LargestRemainder::new(Quota::Droop);
+		assert_eq!(Err(AllocationError::NoParties), allocator.try_allocate_seats(5, &[]));
+		assert_eq!(Err(AllocationError::NoVotes), allocator.try_allocate_seats(5, &[0, 0]));
+		assert_eq!(Ok(vec![0, 0]), allocator.try_allocate_seats(0, &[10, 0]));
+		let huge = usize::MAX - 1;
+		assert_eq!(Err(AllocationError::Overflow), allocator.try_allocate_seats(5, &[huge, 2]));
+	}
+
+	#[test]
+	fn imperiali_overallocation() {
+		let allocator = LargestRemainder::new(Quota::Imperiali);
+		assert_eq!(vec![1, 1], allocator.allocate_seats(2, vec![50, 50]));
+		assert_eq!(vec![5, 2, 2, 1, 0, 0], allocator.allocate_seats(10, example_votes()));
+		assert_eq!(vec![2, 0], allocator.allocate_seats(2, vec![100, 0]));
+	}
+
+	#[test]
+	fn more_seats_than_quotas() {
+		// Parties without votes never get a remainder seat.
+		let droop = LargestRemainder::new(Quota::Droop);
+		assert_eq!(vec![10, 0], droop.allocate_seats(10, vec![3, 0]));
+		assert_eq!(vec![4, 3, 0], droop.allocate_seats(7, vec![3, 2, 0]));
+	}
+}
@@ -1,10 +1,180 @@
+extern crate num_bigint;
 extern crate num_rational;
+extern crate num_traits;
 
+use std::error::Error;
+use std::fmt;
+
+pub mod approval;
+pub mod ballot;
+pub mod biproportional;
+pub mod bounds;
+pub mod budgeting;
+pub mod compensation;
+pub mod condorcet;
+pub mod degressive;
+pub mod equal_shares;
 pub mod highest_averages;
+pub mod irv;
+pub mod largest_remainder;
+pub mod mmp;
+pub mod parallel;
+pub mod phragmen;
+pub mod positional;
+pub mod rated;
+pub mod signpost;
+pub mod stv;
+pub mod threshold;
+pub mod tie;
+
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational};
+
+use ballot::Profile;
+use tie::Tie;
+
+/// The reasons a seat allocation can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AllocationError {
+	/// There are no parties to allocate seats to.
+	NoParties,
+	/// None of the parties received any votes.
+	NoVotes,
+	/// The number of votes or seats is too large to calculate with.
+	Overflow,
+	/// There is a tie for the last seats that was not broken.
+	Tie(Tie),
+	/// No allocation satisfies all constraints.
+	Infeasible,
+	/// No allocation was found within the iteration limit, though one may
+	/// exist.
+	NotConverged,
+	/// The votes don't have an entry for every party or district.
+	MismatchedVotes,
+	/// The divisors of a custom sequence are negative or decrease.
+	InvalidDivisors,
+}
+
+impl fmt::Display for AllocationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			AllocationError::NoParties => write!(f, "no parties to allocate seats to"),
+			AllocationError::NoVotes => write!(f, "none of the parties received any votes"),
+			AllocationError::Overflow => write!(f, "arithmetic overflow"),
+			AllocationError::Infeasible => write!(f, "no allocation satisfies the constraints"),
+			AllocationError::NotConverged => write!(f, "no allocation found within the limit"),
+			AllocationError::InvalidDivisors => {
+				write!(f, "divisors must not be negative or decrease")
+			}
+			AllocationError::MismatchedVotes => {
+				write!(f, "votes missing for some parties or districts")
+			}
+			AllocationError::Tie(ref tie) => write!(
+				f,
+				"unresolved tie between parties {:?} for {} seat(s)",
+				tie.parties, tie.seats
+			),
+		}
+	}
+}
+
+impl Error for AllocationError {}
 
 /// A trait for seat allocation algorithms.
 pub trait AllocateSeats {
+	/// Calculates the number of seats per party given a slice of the number
+	/// of votes per party.
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError>;
+
 	/// Calculates the number of seats per party given a vector of the number
 	/// of votes per party.
-	fn allocate_seats(&self, nb_seats: usize, parties: Vec<usize>) -> Vec<usize>;
+	/// Panics if the allocation fails, see [AllocateSeats::try_allocate_seats].
+	fn allocate_seats(&self, nb_seats: usize, parties: Vec<usize>) -> Vec<usize> {
+		match self.try_allocate_seats(nb_seats, &parties) {
+			Ok(seats) => seats,
+			Err(e) => panic!("seat allocation failed: {}", e),
+		}
+	}
+}
+
+/// The reasons scoring candidates can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ScoringError {
+	/// There are no candidates to score.
+	NoCandidates,
+}
+
+impl fmt::Display for ScoringError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ScoringError::NoCandidates => write!(f, "no candidates to score"),
+		}
+	}
+}
+
+impl Error for ScoringError {}
+
+/// A trait for rules that score candidates from ranked ballots.
+pub trait ScoreCandidates {
+	/// Calculates the score of every candidate in the profile.
+	fn try_score_candidates(&self, profile: &Profile) -> Result<Vec<BigRational>, ScoringError>;
+
+	/// Calculates the score of every candidate in the profile.
+	/// Panics if the scoring fails, see [ScoreCandidates::try_score_candidates].
+	fn score_candidates(&self, profile: &Profile) -> Vec<BigRational> {
+		match self.try_score_candidates(profile) {
+			Ok(scores) => scores,
+			Err(e) => panic!("scoring candidates failed: {}", e),
+		}
+	}
+
+	/// The candidates with the highest score.
+	/// Panics if the scoring fails, see [ScoreCandidates::try_score_candidates].
+	fn winners(&self, profile: &Profile) -> Vec<usize> {
+		let scores = self.score_candidates(profile);
+		let max = scores.iter().max();
+		(0..scores.len()).filter(|c| Some(&scores[*c]) == max).collect()
+	}
+}
+
+/// Check the votes per party before allocating the given number of seats.
+fn check_votes(nb_seats: usize, parties: &[usize]) -> Result<(), AllocationError> {
+	if parties.is_empty() {
+		return Err(AllocationError::NoParties);
+	}
+	if nb_seats > 0 && parties.iter().all(|v| *v == 0) {
+		return Err(AllocationError::NoVotes);
+	}
+	Ok(())
+}
+
+/// Convert a number of votes or seats to calculate with rationals.
+fn to_isize(n: usize) -> Result<isize, AllocationError> {
+	if n > isize::MAX as usize {
+		Err(AllocationError::Overflow)
+	} else {
+		Ok(n as isize)
+	}
+}
+
+/// Add up votes or seats.
+fn checked_sum<I: IntoIterator<Item = usize>>(values: I) -> Result<usize, AllocationError> {
+	values
+		.into_iter()
+		.try_fold(0usize, |sum, v| sum.checked_add(v))
+		.ok_or(AllocationError::Overflow)
+}
+
+/// Convert a number of votes to a big rational.
+fn big(n: usize) -> BigRational {
+	BigRational::from_integer(BigInt::from(n))
+}
+
+/// Convert a rational to a big rational.
+fn to_big(r: Rational) -> BigRational {
+	BigRational::new(BigInt::from(*r.numer()), BigInt::from(*r.denom()))
 }
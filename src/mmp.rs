@@ -0,0 +1,227 @@
+use num_rational::Rational;
+
+use super::{AllocateSeats, AllocationError};
+use highest_averages::HighestAverages;
+
+/// How the seats of parties that won more constituencies than their share of
+/// the list votes are dealt with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Overhang {
+	/// The parties keep their overhang seats, which are added to the house.
+	Keep,
+	/// Balance seats are added to the house until every party's share covers
+	/// its constituency seats, as in Germany from 2013 to 2020.
+	Balance,
+	/// The constituency winners of a party with the lowest share of the votes
+	/// lose their seats until the party's share covers the rest, as in the
+	/// German reform of 2023.
+	Forfeit,
+}
+
+/// The winner of a constituency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Constituency {
+	/// The party of the winner, or None for an independent.
+	pub party: Option<usize>,
+	/// The share of the votes of the winner, to rank the winners of a party.
+	pub share: Rational,
+}
+
+/// The outcome of a mixed-member proportional election.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MmpResult {
+	/// The total number of seats per party.
+	pub seats: Vec<usize>,
+	/// The number of constituency seats per party.
+	pub constituency_seats: Vec<usize>,
+	/// The number of list seats per party.
+	pub list_seats: Vec<usize>,
+	/// The number of seats per party beyond its share of the house.
+	pub overhang: Vec<usize>,
+	/// The number of seats added to the house to balance the overhang.
+	pub balance_seats: usize,
+	/// The number of constituencies won by independents.
+	pub independents: usize,
+	/// The constituencies whose winner lost the seat.
+	pub forfeited: Vec<usize>,
+	/// The size of the house, including the independents.
+	pub nb_seats: usize,
+}
+
+/// Implements mixed-member proportional representation, where the list seats
+/// top up the constituency seats of every party to its share of the list
+/// votes. Independents keep their seats, which are taken from the house
+/// before the list votes are apportioned. Parties without list votes keep
+/// their constituency seats, unless they are forfeited. Parties below a
+/// threshold can be given zero list votes.
+/// For more info: https://en.wikipedia.org/wiki/Mixed-member_proportional_representation
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mmp {
+	method: HighestAverages,
+	overhang: Overhang,
+	cap: Option<usize>,
+}
+
+impl Mmp {
+	/// Apportion the list votes by the given method, usually Sainte-Laguë in
+	/// Germany and D'Hondt elsewhere.
+	pub fn new(method: HighestAverages, overhang: Overhang) -> Mmp {
+		Mmp {
+			method,
+			overhang,
+			cap: None,
+		}
+	}
+
+	/// Limit the size of the house when adding balance seats. Overhang seats
+	/// that are not balanced by then are kept. The default is no limit.
+	pub fn with_cap(self, cap: usize) -> Mmp {
+		Mmp {
+			cap: Some(cap),
+			..self
+		}
+	}
+
+	/// Allocate the given number of seats by the list votes per party and the
+	/// winners of the constituencies. Fails with
+	/// [AllocationError::MismatchedVotes] if a winner's party has no list votes.
+	pub fn allocate(
+		&self,
+		nb_seats: usize,
+		list_votes: &[usize],
+		constituencies: &[Constituency],
+	) -> Result<MmpResult, AllocationError> {
+		if constituencies.iter().any(|c| c.party.is_some_and(|p| p >= list_votes.len())) {
+			return Err(AllocationError::MismatchedVotes);
+		}
+		let mut won = vec![0; list_votes.len()];
+		let mut independents = 0;
+		for constituency in constituencies.iter() {
+			match constituency.party {
+				Some(party) => won[party] += 1,
+				None => independents += 1,
+			}
+		}
+		let available = nb_seats.checked_sub(independents).ok_or(AllocationError::Infeasible)?;
+		let share = self.method.try_allocate_seats(available, list_votes)?;
+
+		let mut size = available;
+		let mut forfeited = Vec::new();
+		let mut constituency_seats = won.clone();
+		let share = match self.overhang {
+			Overhang::Keep => share,
+			Overhang::Balance => {
+				// Parties without list votes can't be balanced.
+				let covered = |share: &[usize]| {
+					(0..won.len()).all(|p| list_votes[p] == 0 || share[p] >= won[p])
+				};
+				let limit = self.cap.map(|cap| cap.saturating_sub(independents));
+				let mut share = share;
+				while !covered(&share) && limit.is_none_or(|l| size < l) {
+					size += 1;
+					share = self.method.try_allocate_seats(size, list_votes)?;
+				}
+				share
+			}
+			Overhang::Forfeit => {
+				for (party, seats) in constituency_seats.iter_mut().enumerate() {
+					if *seats <= share[party] {
+						continue;
+					}
+					let mut winners: Vec<usize> = (0..constituencies.len())
+						.filter(|c| constituencies[*c].party == Some(party))
+						.collect();
+					winners.sort_by(|a, b| constituencies[*b].share.cmp(&constituencies[*a].share));
+					forfeited.extend_from_slice(&winners[share[party]..]);
+					*seats = share[party];
+				}
+				forfeited.sort();
+				share
+			}
+		};
+
+		let overhang: Vec<usize> = constituency_seats
+			.iter()
+			.zip(share.iter())
+			.map(|(c, s)| c.saturating_sub(*s))
+			.collect();
+		let seats: Vec<usize> = share.iter().zip(overhang.iter()).map(|(s, o)| s + o).collect();
+		let list_seats = seats.iter().zip(constituency_seats.iter()).map(|(s, c)| s - c).collect();
+		Ok(MmpResult {
+			nb_seats: seats.iter().sum::<usize>() + independents,
+			seats,
+			constituency_seats,
+			list_seats,
+			overhang,
+			balance_seats: size - available,
+			independents,
+			forfeited,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::Method;
+
+	fn constituencies(parties: &[Option<usize>]) -> Vec<Constituency> {
+		parties
+			.iter()
+			.enumerate()
+			.map(|(i, party)| Constituency {
+				party: *party,
+				share: Rational::new(50 - i as isize, 100),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn overhang() {
+		let votes = [460, 350, 200];
+		let won =
+			constituencies(&[Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), None]);
+		let sainte_lague = HighestAverages::new(Method::SainteLague);
+
+		let keep = Mmp::new(sainte_lague, Overhang::Keep).allocate(10, &votes, &won).unwrap();
+		assert_eq!(vec![6, 3, 2], keep.seats);
+		assert_eq!(vec![2, 0, 0], keep.overhang);
+		assert_eq!(vec![0, 2, 2], keep.list_seats);
+		assert_eq!(12, keep.nb_seats);
+
+		let balance = Mmp::new(sainte_lague, Overhang::Balance).allocate(10, &votes, &won).unwrap();
+		assert_eq!(vec![6, 4, 2], balance.seats);
+		assert_eq!(vec![0, 0, 0], balance.overhang);
+		assert_eq!(3, balance.balance_seats);
+		assert_eq!(13, balance.nb_seats);
+
+		let capped = Mmp::new(sainte_lague, Overhang::Balance)
+			.with_cap(11)
+			.allocate(10, &votes, &won)
+			.unwrap();
+		assert_eq!(vec![6, 3, 2], capped.seats);
+		assert_eq!(vec![1, 0, 0], capped.overhang);
+		assert_eq!(1, capped.balance_seats);
+		assert_eq!(12, capped.nb_seats);
+	}
+
+	#[test]
+	fn forfeit() {
+		let votes = [460, 350, 200];
+		let won =
+			constituencies(&[Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), None]);
+		let sainte_lague = HighestAverages::new(Method::SainteLague);
+		let forfeit = Mmp::new(sainte_lague, Overhang::Forfeit).allocate(10, &votes, &won).unwrap();
+		assert_eq!(vec![4, 3, 2], forfeit.seats);
+		assert_eq!(vec![4, 1, 0], forfeit.constituency_seats);
+		assert_eq!(vec![4, 5], forfeit.forfeited);
+		assert_eq!(10, forfeit.nb_seats);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let mmp = Mmp::new(HighestAverages::new(Method::SainteLague), Overhang::Keep);
+		let won = constituencies(&[Some(0), Some(3)]);
+		assert_eq!(Err(AllocationError::MismatchedVotes), mmp.allocate(10, &[460, 350], &won));
+	}
+}
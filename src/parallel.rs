@@ -0,0 +1,158 @@
+use super::{AllocateSeats, AllocationError};
+
+/// How the votes in the single-member districts affect the list tier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transfer {
+	/// The tiers are independent, which is parallel voting.
+	None,
+	/// The votes for the losing candidates are added to the list votes of
+	/// their party, as in Hungary from 1990 to 2010.
+	Losers,
+	/// In addition to the votes for the losing candidates, the votes of the
+	/// winner beyond the one more than the runner-up needed are added to the
+	/// list votes of their party, as in Hungary since 2014.
+	Winners,
+	/// The votes of the runner-up plus one are subtracted from the list votes
+	/// of the winner's party, the scorporo used in Italy from 1993 to 2005.
+	Scorporo,
+}
+
+/// The outcome of an election with single-member districts and a list tier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParallelResult {
+	/// The party that won every district.
+	pub winners: Vec<usize>,
+	/// The number of districts won per party.
+	pub district_seats: Vec<usize>,
+	/// The list votes per party after the transfers.
+	pub list_votes: Vec<usize>,
+	/// The number of list seats per party.
+	pub list_seats: Vec<usize>,
+	/// The total number of seats per party.
+	pub seats: Vec<usize>,
+}
+
+/// Implements elections where single-member districts are won by plurality
+/// and the list seats are allocated by the list votes, either independently
+/// as in parallel voting, or after transferring votes from the districts to
+/// partially compensate for their results. Ties in a district are broken in
+/// favour of the party listed first.
+/// For more info: https://en.wikipedia.org/wiki/Parallel_voting
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Parallel<A> {
+	allocator: A,
+	transfer: Transfer,
+}
+
+impl<A: AllocateSeats> Parallel<A> {
+	/// Allocate the list seats with the given allocator.
+	pub fn new(allocator: A, transfer: Transfer) -> Parallel<A> {
+		Parallel {
+			allocator,
+			transfer,
+		}
+	}
+
+	/// Allocate the given number of list seats given the list votes per party
+	/// and the votes per party in every district. Fails with
+	/// [AllocationError::MismatchedVotes] if a district doesn't have votes for
+	/// every party.
+	pub fn allocate(
+		&self,
+		nb_list_seats: usize,
+		list_votes: &[usize],
+		districts: &[Vec<usize>],
+	) -> Result<ParallelResult, AllocationError> {
+		if list_votes.is_empty() {
+			return Err(AllocationError::NoParties);
+		}
+		if districts.iter().any(|d| d.len() != list_votes.len()) {
+			return Err(AllocationError::MismatchedVotes);
+		}
+		let add = |votes: &mut usize, n: usize| -> Result<(), AllocationError> {
+			*votes = votes.checked_add(n).ok_or(AllocationError::Overflow)?;
+			Ok(())
+		};
+		let mut winners = Vec::with_capacity(districts.len());
+		let mut district_seats = vec![0; list_votes.len()];
+		let mut votes = list_votes.to_vec();
+		for district in districts.iter() {
+			let mut ranked: Vec<usize> = (0..district.len()).collect();
+			ranked.sort_by(|a, b| district[*b].cmp(&district[*a]));
+			let winner = ranked[0];
+			if district[winner] == 0 {
+				return Err(AllocationError::NoVotes);
+			}
+			let runner_up = ranked.get(1).map(|p| district[*p]).unwrap_or(0);
+			winners.push(winner);
+			district_seats[winner] += 1;
+
+			match self.transfer {
+				Transfer::None => {}
+				Transfer::Losers | Transfer::Winners => {
+					for p in ranked[1..].iter() {
+						add(&mut votes[*p], district[*p])?;
+					}
+					if self.transfer == Transfer::Winners {
+						add(&mut votes[winner], (district[winner] - runner_up).saturating_sub(1))?;
+					}
+				}
+				Transfer::Scorporo => {
+					votes[winner] = votes[winner].saturating_sub(runner_up.saturating_add(1));
+				}
+			}
+		}
+
+		let list_seats = self.allocator.try_allocate_seats(nb_list_seats, &votes)?;
+		let seats = list_seats.iter().zip(district_seats.iter()).map(|(l, d)| l + d).collect();
+		Ok(ParallelResult {
+			winners,
+			district_seats,
+			list_votes: votes,
+			list_seats,
+			seats,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::{HighestAverages, Method};
+
+	#[test]
+	fn transfers() {
+		let list_votes = [500, 400, 100];
+		let districts = vec![vec![60, 30, 10], vec![50, 45, 5], vec![40, 45, 15]];
+		let dhondt = HighestAverages::new(Method::DHondt);
+
+		let parallel = Parallel::new(dhondt, Transfer::None).allocate(5, &list_votes, &districts);
+		let parallel = parallel.unwrap();
+		assert_eq!(vec![0, 0, 1], parallel.winners);
+		assert_eq!(vec![2, 1, 0], parallel.district_seats);
+		assert_eq!(vec![3, 2, 0], parallel.list_seats);
+		assert_eq!(vec![5, 3, 0], parallel.seats);
+
+		let losers = Parallel::new(dhondt, Transfer::Losers).allocate(5, &list_votes, &districts);
+		assert_eq!(vec![540, 475, 130], losers.unwrap().list_votes);
+
+		let winners = Parallel::new(dhondt, Transfer::Winners).allocate(5, &list_votes, &districts);
+		assert_eq!(vec![573, 479, 130], winners.unwrap().list_votes);
+
+		let scorporo =
+			Parallel::new(dhondt, Transfer::Scorporo).allocate(5, &list_votes, &districts);
+		let scorporo = scorporo.unwrap();
+		assert_eq!(vec![423, 359, 100], scorporo.list_votes);
+		assert_eq!(vec![3, 2, 0], scorporo.list_seats);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let parallel = Parallel::new(HighestAverages::new(Method::DHondt), Transfer::Losers);
+		let result = parallel.allocate(5, &[10, 20], &[vec![5, 3], vec![4]]);
+		assert_eq!(Err(AllocationError::MismatchedVotes), result);
+		assert_eq!(Err(AllocationError::NoParties), parallel.allocate(5, &[], &[vec![]]));
+		let result = parallel.allocate(5, &[10, usize::MAX], &[vec![5, 3]]);
+		assert_eq!(Err(AllocationError::Overflow), result);
+	}
+}
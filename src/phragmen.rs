@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+
+use super::big;
+use approval::ApprovalError;
+use ballot::ApprovalProfile;
+
+/// The largest committee for which the optimal loads are calculated, as all
+/// subsets of the committee are tried.
+pub const MAX_OPTIMAL_LOADS: usize = 20;
+
+/// The largest number of committees tried by [Phragmen::Leximax].
+pub const MAX_COMMITTEES: usize = 100000;
+
+/// The result of a Phragmén election.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PhragmenResult {
+	/// The elected candidates, in the order they were elected for the
+	/// sequential method.
+	pub winners: Vec<usize>,
+	/// The load carried by every voter of each ballot.
+	pub loads: Vec<BigRational>,
+}
+
+/// Implements Phragmén's methods, electing a committee whose seats, each a
+/// load of 1, can be spread as evenly as possible over their supporters.
+/// For more info: https://en.wikipedia.org/wiki/Phragmen%27s_voting_rules
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phragmen {
+	/// Elect one candidate at a time, each time the candidate whose election
+	/// leads to the lowest maximal load of their supporters. Ties are broken
+	/// in favour of the lowest candidate index.
+	Sequential,
+	/// Elect the committee whose optimal load distribution is
+	/// lexicographically smallest when sorted from highest to lowest load.
+	/// All committees are tried, so this is only feasible for small elections:
+	/// committees are limited to [MAX_OPTIMAL_LOADS] seats, and to
+	/// [MAX_COMMITTEES] committees to choose from.
+	Leximax,
+}
+
+impl Phragmen {
+	/// Elect a committee of the given size. Only candidates approved by some
+	/// voters can be elected.
+	pub fn elect(
+		&self,
+		nb_seats: usize,
+		profile: &ApprovalProfile,
+	) -> Result<PhragmenResult, ApprovalError> {
+		let approved: Vec<usize> = profile
+			.approvals()
+			.iter()
+			.enumerate()
+			.filter(|(_, a)| **a > 0)
+			.map(|(c, _)| c)
+			.collect();
+		if nb_seats > approved.len() {
+			return Err(ApprovalError::NotEnoughCandidates);
+		}
+		match *self {
+			Phragmen::Sequential => Ok(sequential(nb_seats, profile)),
+			Phragmen::Leximax => leximax(nb_seats, profile, &approved),
+		}
+	}
+}
+
+fn sequential(nb_seats: usize, profile: &ApprovalProfile) -> PhragmenResult {
+	let mut result = PhragmenResult {
+		winners: Vec::with_capacity(nb_seats),
+		loads: vec![BigRational::zero(); profile.ballots().len()],
+	};
+	let costs = vec![BigRational::one(); profile.nb_candidates()];
+	extend(profile, &costs, &mut result, |winners, _| winners.len() < nb_seats);
+	result
+}
+
+/// Elect candidates with sequential Phragmén, starting from the given loads,
+/// until no approved candidate is left or the candidate with the lowest load
+/// doesn't fit given the winners so far. Electing a candidate spreads their
+/// cost over the loads of their supporters.
+pub(crate) fn extend<F>(
+	profile: &ApprovalProfile,
+	costs: &[BigRational],
+	result: &mut PhragmenResult,
+	fits: F,
+) where
+	F: Fn(&[usize], usize) -> bool,
+{
+	let ballots = profile.ballots();
+	loop {
+		let mut best: Option<(usize, BigRational)> = None;
+		for c in (0..profile.nb_candidates()).filter(|c| !result.winners.contains(c)) {
+			let mut support = BigRational::zero();
+			let mut load = costs[c].clone();
+			for (ballot, l) in ballots.iter().zip(result.loads.iter()) {
+				if ballot.approves(c) {
+					support += big(ballot.weight);
+					load += big(ballot.weight) * l;
+				}
+			}
+			if support.is_zero() {
+				continue;
+			}
+			load /= support;
+			if best.as_ref().is_none_or(|(_, l)| load < *l) {
+				best = Some((c, load));
+			}
+		}
+		let (c, load) = match best {
+			Some(best) => best,
+			None => return,
+		};
+		if !fits(&result.winners, c) {
+			return;
+		}
+		for (ballot, l) in ballots.iter().zip(result.loads.iter_mut()) {
+			if ballot.approves(c) {
+				*l = load.clone();
+			}
+		}
+		result.winners.push(c);
+	}
+}
+
+fn leximax(
+	nb_seats: usize,
+	profile: &ApprovalProfile,
+	approved: &[usize],
+) -> Result<PhragmenResult, ApprovalError> {
+	// Count the committees, C(n, k), without overflowing.
+	let mut nb_committees = 1usize;
+	for i in 0..nb_seats {
+		nb_committees = nb_committees
+			.checked_mul(approved.len() - i)
+			.map(|c| c / (i + 1))
+			.filter(|c| *c <= MAX_COMMITTEES)
+			.ok_or(ApprovalError::TooLarge)?;
+	}
+	let mut best: Option<PhragmenResult> = None;
+	let mut committee: Vec<usize> = (0..nb_seats).collect();
+	loop {
+		let winners: Vec<usize> = committee.iter().map(|i| approved[*i]).collect();
+		let loads = optimal_loads(profile, &winners)?;
+		let better = match best {
+			Some(ref b) => compare_loads(profile, &loads, &b.loads) == Ordering::Less,
+			None => true,
+		};
+		if better {
+			best = Some(PhragmenResult {
+				winners,
+				loads,
+			});
+		}
+		if !next_combination(&mut committee, approved.len()) {
+			return Ok(best.unwrap());
+		}
+	}
+}
+
+/// Advance to the next combination of indices in lexicographic order, or
+/// return false if this was the last one.
+fn next_combination(indices: &mut [usize], n: usize) -> bool {
+	let k = indices.len();
+	for i in (0..k).rev() {
+		if indices[i] < n - k + i {
+			indices[i] += 1;
+			for j in (i + 1)..k {
+				indices[j] = indices[j - 1] + 1;
+			}
+			return true;
+		}
+	}
+	false
+}
+
+/// Distribute the load of the committee over its supporters such that the
+/// loads are lexicographically minimal. The densest set of members, with the
+/// most seats per supporter, determines the highest load. Those members and
+/// their supporters are removed and the rest is distributed the same way.
+/// Fails if the committee has more than [MAX_OPTIMAL_LOADS] members.
+/// Panics if a member is not approved by any voter.
+pub fn optimal_loads(
+	profile: &ApprovalProfile,
+	committee: &[usize],
+) -> Result<Vec<BigRational>, ApprovalError> {
+	if committee.len() > MAX_OPTIMAL_LOADS {
+		return Err(ApprovalError::TooLarge);
+	}
+	let ballots = profile.ballots();
+	let mut loads = vec![BigRational::zero(); ballots.len()];
+	let mut active = vec![true; ballots.len()];
+	let mut remaining = committee.to_vec();
+	while !remaining.is_empty() {
+		let mut densest: Option<(BigRational, usize)> = None;
+		for set in 1..(1usize << remaining.len()) {
+			let mut support = BigRational::zero();
+			for (ballot, _) in ballots.iter().zip(active.iter()).filter(|(_, a)| **a) {
+				if (0..remaining.len())
+					.any(|i| set & (1 << i) != 0 && ballot.approves(remaining[i]))
+				{
+					support += big(ballot.weight);
+				}
+			}
+			// Approved members always have supporters left, or adding them to
+			// the densest set before would have made it denser.
+			let density = big(set.count_ones() as usize) / support;
+			densest = match densest {
+				Some((d, s)) if d == density => Some((d, s | set)),
+				Some((d, s)) if d > density => Some((d, s)),
+				_ => Some((density, set)),
+			};
+		}
+		let (density, set) = densest.unwrap();
+		let members: Vec<usize> =
+			(0..remaining.len()).filter(|i| set & (1 << i) != 0).map(|i| remaining[i]).collect();
+		for (i, ballot) in ballots.iter().enumerate() {
+			if active[i] && members.iter().any(|c| ballot.approves(*c)) {
+				loads[i] = density.clone();
+				active[i] = false;
+			}
+		}
+		remaining.retain(|c| !members.contains(c));
+	}
+	Ok(loads)
+}
+
+/// Compare the loads of all voters, sorted from highest to lowest.
+fn compare_loads(profile: &ApprovalProfile, a: &[BigRational], b: &[BigRational]) -> Ordering {
+	let sorted = |loads: &[BigRational]| {
+		let mut sorted: Vec<(BigRational, usize)> =
+			loads.iter().cloned().zip(profile.ballots().iter().map(|b| b.weight)).collect();
+		sorted.sort_by(|x, y| y.0.cmp(&x.0));
+		sorted
+	};
+	let (a, b) = (sorted(a), sorted(b));
+	let (mut i, mut j) = (0, 0);
+	let (mut left_a, mut left_b) = (0, 0);
+	while i < a.len() && j < b.len() {
+		if left_a == 0 {
+			left_a = a[i].1;
+		}
+		if left_b == 0 {
+			left_b = b[j].1;
+		}
+		match a[i].0.cmp(&b[j].0) {
+			Ordering::Equal => {}
+			ord => return ord,
+		}
+		let step = left_a.min(left_b);
+		left_a -= step;
+		left_b -= step;
+		if left_a == 0 {
+			i += 1;
+		}
+		if left_b == 0 {
+			j += 1;
+		}
+	}
+	Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::approval_profile;
+
+	fn ratio(n: usize, d: usize) -> BigRational {
+		big(n) / big(d)
+	}
+
+	#[test]
+	fn sequential() {
+		let profile = approval_profile(3, vec![(6, vec![0, 1]), (4, vec![2])]);
+		let result = Phragmen::Sequential.elect(2, &profile).unwrap();
+		assert_eq!(vec![0, 2], result.winners);
+		assert_eq!(vec![ratio(1, 6), ratio(1, 4)], result.loads);
+		assert_eq!(
+			Err(ApprovalError::NotEnoughCandidates),
+			Phragmen::Sequential.elect(4, &profile)
+		);
+	}
+
+	#[test]
+	fn leximax() {
+		let profile =
+			approval_profile(4, vec![(2, vec![0]), (2, vec![2]), (2, vec![1, 2]), (1, vec![1, 3])]);
+		let sequential = Phragmen::Sequential.elect(2, &profile).unwrap();
+		assert_eq!(vec![2, 0], sequential.winners);
+		assert_eq!(vec![ratio(1, 2), ratio(1, 4), ratio(1, 4), ratio(0, 1)], sequential.loads);
+		let leximax = Phragmen::Leximax.elect(2, &profile).unwrap();
+		assert_eq!(vec![1, 2], leximax.winners);
+		assert_eq!(vec![ratio(0, 1), ratio(2, 5), ratio(2, 5), ratio(2, 5)], leximax.loads);
+	}
+
+	#[test]
+	fn loads() {
+		// One member has a single supporter, the other two share the rest.
+		let profile = approval_profile(3, vec![(1, vec![0]), (3, vec![1, 2]), (1, vec![2])]);
+		let loads = optimal_loads(&profile, &[0, 1, 2]);
+		assert_eq!(Ok(vec![ratio(1, 1), ratio(1, 2), ratio(1, 2)]), loads);
+
+		let profile = approval_profile(64, vec![(1, (0..64).collect())]);
+		let committee: Vec<usize> = (0..64).collect();
+		assert_eq!(Err(ApprovalError::TooLarge), optimal_loads(&profile, &committee));
+		assert_eq!(Err(ApprovalError::TooLarge), Phragmen::Leximax.elect(64, &profile));
+		// Small committees can still have too many candidates to choose from.
+		assert_eq!(Err(ApprovalError::TooLarge), Phragmen::Leximax.elect(10, &profile));
+		assert!(Phragmen::Leximax.elect(2, &profile).is_ok());
+	}
+}
@@ -0,0 +1,190 @@
+use num_rational::{BigRational, Rational};
+use num_traits::Zero;
+
+use super::{big, to_big, ScoreCandidates, ScoringError};
+use ballot::{Ballot, Profile};
+
+/// The positional scoring rules.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+	/// n - 1 points for the first preference down to 0 for the last.
+	Borda,
+	/// 1 / k points for the k-th preference.
+	Dowdall,
+	/// Borda for truncated ballots: a ballot ranking m candidates gives m
+	/// points to its first preference down to 1 for its last, and 0 to
+	/// unranked candidates.
+	ModifiedBorda,
+	/// A point for the first preference only.
+	Plurality,
+	/// A point for every candidate except the last preference.
+	AntiPlurality,
+	/// A point for each of the first k preferences.
+	Approval(usize),
+	/// The given points per position, zero for positions beyond the end.
+	Custom(Vec<Rational>),
+}
+
+impl Method {
+	/// The points per position for a ballot that ranks nb_ranked of the
+	/// candidates.
+	pub fn scores(&self, nb_candidates: usize, nb_ranked: usize) -> Vec<Rational> {
+		let n = nb_candidates as isize;
+		(0..n)
+			.map(|i| match *self {
+				Method::Borda => Rational::from_integer(n - 1 - i),
+				Method::Dowdall => Rational::new(1, i + 1),
+				Method::ModifiedBorda => Rational::from_integer((nb_ranked as isize - i).max(0)),
+				Method::Plurality => Rational::from_integer((i == 0) as isize),
+				Method::AntiPlurality => Rational::from_integer((i < n - 1) as isize),
+				Method::Approval(k) => Rational::from_integer((i < k as isize) as isize),
+				Method::Custom(ref scores) => {
+					scores.get(i as usize).cloned().unwrap_or_else(|| Rational::from_integer(0))
+				}
+			})
+			.collect()
+	}
+}
+
+/// Implements positional scoring rules, where every candidate receives points
+/// for the position at which they are ranked. Candidates that are ranked
+/// equally share the points of the positions they take, and candidates that
+/// are not ranked share the points of the remaining positions.
+/// For more info: https://en.wikipedia.org/wiki/Positional_voting
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Positional {
+	method: Method,
+}
+
+impl Positional {
+	pub fn new(method: Method) -> Positional {
+		Positional {
+			method,
+		}
+	}
+
+	/// The points of every candidate on the ballot.
+	fn ballot_scores(&self, ballot: &Ballot, nb_candidates: usize) -> Vec<BigRational> {
+		let mut groups: Vec<Vec<usize>> = Vec::new();
+		let mut ranked = vec![false; nb_candidates];
+		for rank in ballot.ranks.iter() {
+			let mut group = Vec::new();
+			for c in rank.iter() {
+				if !ranked[*c] {
+					ranked[*c] = true;
+					group.push(*c);
+				}
+			}
+			if !group.is_empty() {
+				groups.push(group);
+			}
+		}
+		let nb_ranked = groups.iter().map(|g| g.len()).sum();
+		groups.push((0..nb_candidates).filter(|c| !ranked[*c]).collect());
+
+		let points = self.method.scores(nb_candidates, nb_ranked);
+		let mut scores = vec![BigRational::zero(); nb_candidates];
+		let mut position = 0;
+		for group in groups.into_iter().filter(|g| !g.is_empty()) {
+			let end = position + group.len();
+			let sum = points[position..end].iter().fold(BigRational::zero(), |a, b| a + to_big(*b));
+			let share = sum / big(group.len());
+			for c in group {
+				scores[c] = share.clone();
+			}
+			position = end;
+		}
+		scores
+	}
+}
+
+impl ScoreCandidates for Positional {
+	fn try_score_candidates(&self, profile: &Profile) -> Result<Vec<BigRational>, ScoringError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(ScoringError::NoCandidates);
+		}
+		let mut scores = vec![BigRational::zero(); n];
+		for ballot in profile.ballots().iter() {
+			let weight = big(ballot.weight);
+			for (c, points) in self.ballot_scores(ballot, n).into_iter().enumerate() {
+				scores[c] += &weight * points;
+			}
+		}
+		Ok(scores)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::profile;
+
+	use num_traits::ToPrimitive;
+
+	fn integers(scores: Vec<BigRational>) -> Vec<isize> {
+		scores.into_iter().map(|s| s.to_integer().to_isize().unwrap()).collect()
+	}
+
+	#[test]
+	fn scores() {
+		let r = |n, d| Rational::new(n, d);
+		assert_eq!(vec![r(2, 1), r(1, 1), r(0, 1)], Method::Borda.scores(3, 3));
+		assert_eq!(vec![r(1, 1), r(1, 2), r(1, 3)], Method::Dowdall.scores(3, 3));
+		assert_eq!(vec![r(1, 1), r(0, 1), r(0, 1)], Method::ModifiedBorda.scores(3, 1));
+		assert_eq!(vec![r(1, 1), r(1, 1), r(0, 1)], Method::AntiPlurality.scores(3, 3));
+		assert_eq!(vec![r(1, 1), r(1, 1), r(0, 1)], Method::Approval(2).scores(3, 3));
+		assert_eq!(vec![r(3, 1), r(0, 1)], Method::Custom(vec![r(3, 1)]).scores(2, 2));
+	}
+
+	#[test]
+	fn example_tennessee() {
+		// Memphis, Nashville, Chattanooga, Knoxville.
+		let ballots = vec![
+			Ballot::new(42, vec![0, 1, 2, 3]),
+			Ballot::new(26, vec![1, 2, 3, 0]),
+			Ballot::new(15, vec![2, 3, 1, 0]),
+			Ballot::new(17, vec![3, 2, 1, 0]),
+		];
+		let profile = profile(4, ballots);
+		let borda = Positional::new(Method::Borda);
+		assert_eq!(vec![126, 194, 173, 107], integers(borda.score_candidates(&profile)));
+		assert_eq!(vec![1], borda.winners(&profile));
+		let plurality = Positional::new(Method::Plurality);
+		assert_eq!(vec![42, 26, 15, 17], integers(plurality.score_candidates(&profile)));
+		assert_eq!(vec![0], plurality.winners(&profile));
+		let anti = Positional::new(Method::AntiPlurality);
+		assert_eq!(vec![42, 100, 100, 58], integers(anti.score_candidates(&profile)));
+		assert_eq!(vec![1, 2], anti.winners(&profile));
+	}
+
+	#[test]
+	fn truncated_ballots() {
+		let ballots = vec![Ballot::with_ranks(2, vec![vec![0, 1]]), Ballot::new(1, vec![2])];
+		let profile = profile(3, ballots);
+		let r = |n, d| big(n) / big(d);
+		let borda = Positional::new(Method::Borda).score_candidates(&profile);
+		assert_eq!(vec![r(7, 2), r(7, 2), r(2, 1)], borda);
+		let modified = Positional::new(Method::ModifiedBorda).score_candidates(&profile);
+		assert_eq!(vec![r(3, 1), r(3, 1), r(1, 1)], modified);
+		let dowdall = Positional::new(Method::Dowdall).score_candidates(&profile);
+		assert_eq!(vec![r(23, 12), r(23, 12), r(5, 3)], dowdall);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let profile = Profile::new(Vec::new());
+		let borda = Positional::new(Method::Borda);
+		assert_eq!(Err(ScoringError::NoCandidates), borda.try_score_candidates(&profile));
+	}
+
+	#[test]
+	fn many_candidates() {
+		let ballots = vec![Ballot::new(usize::MAX, vec![0]), Ballot::new(3, vec![1, 0])];
+		let profile = profile(60, ballots);
+		let dowdall = Positional::new(Method::Dowdall).score_candidates(&profile);
+		assert!(dowdall[0] > big(usize::MAX));
+		assert!(dowdall[1] > dowdall[2]);
+		assert_eq!(vec![0], Positional::new(Method::Dowdall).winners(&profile));
+	}
+}
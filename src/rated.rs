@@ -0,0 +1,413 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use num_rational::BigRational;
+
+use super::big;
+use ballot::RatedProfile;
+use tie::Lot;
+
+/// The reasons a count can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RatedError {
+	/// There are no candidates.
+	NoCandidates,
+	/// There are no voters.
+	NoVotes,
+}
+
+impl fmt::Display for RatedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			RatedError::NoCandidates => write!(f, "no candidates"),
+			RatedError::NoVotes => write!(f, "no votes"),
+		}
+	}
+}
+
+impl Error for RatedError {}
+
+/// The result of score voting.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ScoreResult {
+	/// The total score of every candidate.
+	pub totals: Vec<usize>,
+	/// The candidates with the highest total score.
+	pub winners: Vec<usize>,
+}
+
+/// Implements score voting, electing the candidate with the highest total
+/// score.
+/// For more info: https://en.wikipedia.org/wiki/Score_voting
+pub fn score(profile: &RatedProfile) -> Result<ScoreResult, RatedError> {
+	if profile.nb_candidates() == 0 {
+		return Err(RatedError::NoCandidates);
+	}
+	let totals = totals(profile);
+	let max = *totals.iter().max().unwrap();
+	let winners = (0..totals.len()).filter(|c| totals[*c] == max).collect();
+	Ok(ScoreResult {
+		totals,
+		winners,
+	})
+}
+
+fn totals(profile: &RatedProfile) -> Vec<usize> {
+	let mut totals = vec![0; profile.nb_candidates()];
+	for ballot in profile.ballots().iter() {
+		for (total, score) in totals.iter_mut().zip(ballot.scores.iter()) {
+			*total += ballot.weight * score;
+		}
+	}
+	totals
+}
+
+/// The result of STAR voting.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StarResult {
+	/// The total score of every candidate.
+	pub totals: Vec<usize>,
+	/// The candidates in the automatic runoff.
+	pub finalists: Vec<usize>,
+	/// The number of voters preferring each finalist.
+	pub preferences: Vec<usize>,
+	/// The number of voters scoring the finalists equally.
+	pub no_preference: usize,
+	pub winner: usize,
+}
+
+/// Implements STAR voting, Score Then Automatic Runoff: the two candidates
+/// with the highest total scores go to a runoff, which is won by the finalist
+/// preferred by most voters. Ties are broken by the official protocol, and
+/// finally by lot.
+/// For more info: https://www.starvoting.org/ties
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Star {
+	seed: u64,
+}
+
+impl Star {
+	/// Create a count that draws lots with the given seed.
+	pub fn new(seed: u64) -> Star {
+		Star {
+			seed,
+		}
+	}
+
+	pub fn count(&self, profile: &RatedProfile) -> Result<StarResult, RatedError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(RatedError::NoCandidates);
+		}
+		let mut lot = Lot::new(self.seed);
+		let totals = totals(profile);
+		let max_scores: Vec<usize> =
+			(0..n).map(|c| profile.distribution(c)[profile.max_score()]).collect();
+
+		// Scoring round: ties are broken by head-to-head wins among the tied
+		// candidates, then by the number of maximum scores.
+		let mut finalists: Vec<usize> = Vec::with_capacity(2);
+		while finalists.len() < n.min(2) {
+			let remaining: Vec<usize> = (0..n).filter(|c| !finalists.contains(c)).collect();
+			let best = remaining.iter().map(|c| totals[*c]).max().unwrap();
+			let tied: Vec<usize> = remaining.into_iter().filter(|c| totals[*c] == best).collect();
+			let wins: Vec<usize> = tied
+				.iter()
+				.map(|a| {
+					tied.iter()
+						.filter(|b| {
+							let (for_a, for_b) = preferences(profile, *a, **b);
+							for_a > for_b
+						})
+						.count()
+				})
+				.collect();
+			let max_wins = *wins.iter().max().unwrap();
+			let tied: Vec<usize> =
+				(0..tied.len()).filter(|i| wins[*i] == max_wins).map(|i| tied[i]).collect();
+			finalists.push(break_tie(tied, &max_scores, &mut lot));
+		}
+		if n == 1 {
+			return Ok(StarResult {
+				totals,
+				finalists,
+				preferences: vec![profile.nb_voters()],
+				no_preference: 0,
+				winner: 0,
+			});
+		}
+
+		// Runoff: ties are broken by the total score, then by the number of
+		// maximum scores.
+		let (a, b) = (finalists[0], finalists[1]);
+		let (for_a, for_b) = preferences(profile, a, b);
+		let winner = match for_a.cmp(&for_b) {
+			Ordering::Greater => a,
+			Ordering::Less => b,
+			Ordering::Equal => match totals[a].cmp(&totals[b]) {
+				Ordering::Greater => a,
+				Ordering::Less => b,
+				Ordering::Equal => break_tie(vec![a, b], &max_scores, &mut lot),
+			},
+		};
+		Ok(StarResult {
+			totals,
+			finalists,
+			preferences: vec![for_a, for_b],
+			no_preference: profile.nb_voters() - for_a - for_b,
+			winner,
+		})
+	}
+}
+
+/// The number of voters scoring a higher than b, and b higher than a.
+fn preferences(profile: &RatedProfile, a: usize, b: usize) -> (usize, usize) {
+	let mut result = (0, 0);
+	for ballot in profile.ballots().iter() {
+		match ballot.scores[a].cmp(&ballot.scores[b]) {
+			Ordering::Greater => result.0 += ballot.weight,
+			Ordering::Less => result.1 += ballot.weight,
+			Ordering::Equal => {}
+		}
+	}
+	result
+}
+
+/// Pick the tied candidate with the most maximum scores, or draw lots.
+fn break_tie(tied: Vec<usize>, max_scores: &[usize], lot: &mut Lot) -> usize {
+	let most = tied.iter().map(|c| max_scores[*c]).max().unwrap();
+	let tied: Vec<usize> = tied.into_iter().filter(|c| max_scores[*c] == most).collect();
+	if tied.len() == 1 {
+		tied[0]
+	} else {
+		tied[lot.below(tied.len())]
+	}
+}
+
+/// The result of a judgment method.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JudgmentResult {
+	/// The median score of every candidate, the lower one for an even number
+	/// of voters.
+	pub medians: Vec<usize>,
+	/// The full ranking from winners to losers, with equally ranked
+	/// candidates grouped together.
+	pub ranking: Vec<Vec<usize>>,
+}
+
+/// The methods that rank candidates by their median score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Judgment {
+	/// Majority Judgment, which breaks ties between equal medians by
+	/// repeatedly removing the median score.
+	/// For more info: https://en.wikipedia.org/wiki/Majority_judgment
+	Majority,
+	/// Usual Judgment, which breaks ties between equal medians by the shares
+	/// of voters scoring the candidate above and below the median, see
+	/// [usual_score].
+	Usual,
+}
+
+impl Judgment {
+	pub fn rank(&self, profile: &RatedProfile) -> Result<JudgmentResult, RatedError> {
+		let n = profile.nb_candidates();
+		if n == 0 {
+			return Err(RatedError::NoCandidates);
+		}
+		if profile.nb_voters() == 0 {
+			return Err(RatedError::NoVotes);
+		}
+		let distributions: Vec<Vec<usize>> = (0..n).map(|c| profile.distribution(c)).collect();
+		let medians = distributions.iter().map(|d| median(d)).collect();
+		let compare = |a: usize, b: usize| match *self {
+			Judgment::Majority => compare_majority(&distributions[a], &distributions[b]),
+			Judgment::Usual => usual_score(&distributions[a]).cmp(&usual_score(&distributions[b])),
+		};
+		let mut order: Vec<usize> = (0..n).collect();
+		order.sort_by(|a, b| compare(*b, *a));
+		let mut ranking: Vec<Vec<usize>> = Vec::new();
+		for c in order {
+			match ranking.last_mut() {
+				Some(ref mut group) if compare(group[0], c) == Ordering::Equal => group.push(c),
+				_ => ranking.push(vec![c]),
+			}
+		}
+		Ok(JudgmentResult {
+			medians,
+			ranking,
+		})
+	}
+}
+
+/// The lower median of the scores with the given distribution.
+fn median(distribution: &[usize]) -> usize {
+	let n: usize = distribution.iter().sum();
+	score_at(distribution, (n.max(1) - 1) / 2).0
+}
+
+/// The score at the given position when all scores are sorted, and the first
+/// and last position with that score.
+fn score_at(distribution: &[usize], position: usize) -> (usize, usize, usize) {
+	let mut start = 0;
+	for (score, count) in distribution.iter().enumerate() {
+		if position < start + count {
+			return (score, start, start + count - 1);
+		}
+		start += count;
+	}
+	unreachable!("position beyond the number of scores")
+}
+
+/// Compare the sequences of medians when repeatedly removing the median. For
+/// the same number of voters, the medians are removed at the same positions
+/// for every candidate: the lower median first, then alternating outwards.
+fn compare_majority(a: &[usize], b: &[usize]) -> Ordering {
+	let n: usize = a.iter().sum();
+	if n == 0 {
+		return Ordering::Equal;
+	}
+	let median = (n - 1) / 2;
+
+	// Find the first difference on both sides of the median, skipping runs of
+	// equal scores.
+	let mut below = None;
+	let mut position = median;
+	loop {
+		let (score_a, start_a, _) = score_at(a, position);
+		let (score_b, start_b, _) = score_at(b, position);
+		if score_a != score_b {
+			below = Some(position);
+			break;
+		}
+		match start_a.max(start_b) {
+			0 => break,
+			start => position = start - 1,
+		}
+	}
+	let mut above = None;
+	let mut position = median + 1;
+	while position < n {
+		let (score_a, _, end_a) = score_at(a, position);
+		let (score_b, _, end_b) = score_at(b, position);
+		if score_a != score_b {
+			above = Some(position);
+			break;
+		}
+		position = end_a.min(end_b) + 1;
+	}
+
+	// The step at which the median is removed at each position.
+	let step = |position: usize| {
+		let odd = n % 2 == 1;
+		if position <= median {
+			let j = median - position;
+			if j == 0 {
+				0
+			} else if odd {
+				2 * j - 1
+			} else {
+				2 * j
+			}
+		} else {
+			let j = position - median;
+			if odd {
+				2 * j
+			} else {
+				2 * j - 1
+			}
+		}
+	};
+	let first = match (below, above) {
+		(Some(p), Some(q)) => Some(if step(p) < step(q) {
+			p
+		} else {
+			q
+		}),
+		(p, q) => p.or(q),
+	};
+	match first {
+		Some(p) => score_at(a, p).0.cmp(&score_at(b, p).0),
+		None => Ordering::Equal,
+	}
+}
+
+/// The Usual Judgment score of a candidate with the given distribution of
+/// scores: the median plus (p - q) / (2 (1 - p - q)), where p and q are the
+/// shares of voters scoring above and below the median.
+/// For more info: https://en.wikipedia.org/wiki/Usual_judgment
+pub fn usual_score(distribution: &[usize]) -> BigRational {
+	let median = median(distribution);
+	let above: usize = distribution[median + 1..].iter().sum();
+	let below: usize = distribution[..median].iter().sum();
+	let at = distribution[median];
+	big(median) + (big(above) - big(below)) / (big(2) * big(at))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::rated_profile;
+
+	#[test]
+	fn score_and_star() {
+		let profile =
+			rated_profile(5, vec![(4, vec![5, 0, 3]), (3, vec![0, 5, 4]), (2, vec![1, 2, 5])]);
+		let result = score(&profile).unwrap();
+		assert_eq!(vec![22, 19, 34], result.totals);
+		assert_eq!(vec![2], result.winners);
+
+		let result = Star::new(0).count(&profile).unwrap();
+		assert_eq!(vec![2, 0], result.finalists);
+		assert_eq!(vec![5, 4], result.preferences);
+		assert_eq!(0, result.no_preference);
+		assert_eq!(2, result.winner);
+	}
+
+	#[test]
+	fn star_ties() {
+		// 1 and 2 tie for the second finalist and 1 wins head-to-head, even
+		// though 2 has more maximum scores.
+		let profile =
+			rated_profile(5, vec![(2, vec![5, 3, 2]), (1, vec![5, 0, 5]), (1, vec![4, 3, 0])]);
+		let result = Star::new(0).count(&profile).unwrap();
+		assert_eq!(vec![19, 9, 9], result.totals);
+		assert_eq!(vec![0, 1], result.finalists);
+		assert_eq!(0, result.winner);
+
+		// The runoff is tied, and so are the scores, so the finalist with
+		// the most maximum scores wins.
+		let tied = rated_profile(5, vec![(1, vec![5, 3]), (1, vec![2, 4])]);
+		let result = Star::new(0).count(&tied).unwrap();
+		assert_eq!(vec![1, 1], result.preferences);
+		assert_eq!(0, result.winner);
+	}
+
+	#[test]
+	fn judgment() {
+		let profile = rated_profile(
+			4,
+			vec![(2, vec![4, 2]), (1, vec![0, 2]), (1, vec![0, 1]), (1, vec![2, 1])],
+		);
+		let majority = Judgment::Majority.rank(&profile).unwrap();
+		assert_eq!(vec![2, 2], majority.medians);
+		assert_eq!(vec![vec![1], vec![0]], majority.ranking);
+		let usual = Judgment::Usual.rank(&profile).unwrap();
+		assert_eq!(vec![vec![0], vec![1]], usual.ranking);
+		assert_eq!(big(5) / big(3), usual_score(&profile.distribution(1)));
+
+		// Equal distributions are tied.
+		let tied = rated_profile(2, vec![(1, vec![0, 2]), (1, vec![2, 0])]);
+		assert_eq!(vec![vec![0, 1]], Judgment::Majority.rank(&tied).unwrap().ranking);
+	}
+
+	#[test]
+	fn majority_values() {
+		// Sorted scores 0 0 2 4 4 give medians 2 0 4 0 4, and 1 1 2 2 2
+		// give 2 1 2 1 2.
+		assert_eq!(Ordering::Less, compare_majority(&[2, 0, 1, 0, 2], &[0, 2, 3, 0, 0]));
+		// Sorted scores 1 2 3 3 give medians 2 3 1 3, and 0 2 3 4 give 2 3 0 4.
+		assert_eq!(Ordering::Greater, compare_majority(&[0, 1, 1, 2, 0], &[1, 0, 1, 1, 1]));
+		assert_eq!(Ordering::Equal, compare_majority(&[1, 1], &[1, 1]));
+	}
+}
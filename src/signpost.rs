@@ -0,0 +1,317 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational};
+use num_traits::{pow, ToPrimitive, Zero};
+
+use super::{big, check_votes, AllocateSeats, AllocationError};
+use tie::{Tie, TieBreak};
+
+/// The signposts of a divisor method: a party gets its n-th seat once its
+/// votes divided by the common divisor reach the n-th signpost, which lies
+/// between n - 1 and n.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Signpost {
+	/// The signpost n - 1 + r for the given r between 0 and 1, such as 1 for
+	/// D'Hondt, 1/2 for Sainte-Laguë and 0 for Adams. Any other r fails with
+	/// [AllocationError::InvalidDivisors].
+	Stationary(Rational),
+	/// The power mean of n - 1 and n with the given exponent, such as 1 for
+	/// Sainte-Laguë, 0 for the geometric mean of Huntington-Hill and -1 for the
+	/// harmonic mean of Dean.
+	PowerMean(isize),
+}
+
+impl Signpost {
+	/// The power the signposts are raised to in order to keep them rational.
+	fn exponent(&self) -> usize {
+		match *self {
+			Signpost::Stationary(_) => 1,
+			Signpost::PowerMean(0) => 2,
+			Signpost::PowerMean(p) => p.unsigned_abs(),
+		}
+	}
+
+	/// The n-th signpost, starting at 1, raised to the exponent.
+	fn value(&self, n: usize) -> BigRational {
+		let e = self.exponent();
+		match *self {
+			Signpost::Stationary(r) => {
+				big(n - 1) + BigRational::new(BigInt::from(*r.numer()), BigInt::from(*r.denom()))
+			}
+			Signpost::PowerMean(0) => big(n - 1) * big(n),
+			Signpost::PowerMean(p) if p > 0 => (pow(big(n - 1), e) + pow(big(n), e)) / big(2),
+			Signpost::PowerMean(_) if n == 1 => BigRational::zero(),
+			Signpost::PowerMean(_) => {
+				big(2) / (pow(big(n - 1), e).recip() + pow(big(n), e).recip())
+			}
+		}
+	}
+}
+
+/// The priority of a party for its next seat, or to keep its last seat: its
+/// votes divided by the signpost, raised to the exponent. A signpost of zero
+/// gives an infinite priority, which is represented as None.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Priority(Option<BigRational>);
+
+impl Ord for Priority {
+	fn cmp(&self, other: &Priority) -> Ordering {
+		match (&self.0, &other.0) {
+			(None, None) => Ordering::Equal,
+			(None, Some(_)) => Ordering::Greater,
+			(Some(_), None) => Ordering::Less,
+			(Some(a), Some(b)) => a.cmp(b),
+		}
+	}
+}
+
+impl PartialOrd for Priority {
+	fn partial_cmp(&self, other: &Priority) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Implements divisor methods by their signposts. Instead of building the
+/// quotient table, the seats are rounded for an estimate of the common
+/// divisor, and then the parties closest to a signpost gain or lose seats
+/// until all seats are allocated. This gives the same seats as
+/// [HighestAverages](::highest_averages::HighestAverages) with the matching
+/// divisors, including how a tie for the last seats is broken.
+/// For more info: https://en.wikipedia.org/wiki/Highest_averages_method
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DivisorSearch {
+	signpost: Signpost,
+	tie_break: TieBreak,
+}
+
+impl DivisorSearch {
+	pub fn new(signpost: Signpost) -> DivisorSearch {
+		DivisorSearch {
+			signpost,
+			tie_break: TieBreak::default(),
+		}
+	}
+
+	/// Set the policy used to break a tie for the last seats.
+	/// The default is to favour the party listed first.
+	pub fn with_tie_break(self, tie_break: TieBreak) -> DivisorSearch {
+		DivisorSearch {
+			tie_break,
+			..self
+		}
+	}
+
+	/// The priority of a party with the given votes raised to the exponent for
+	/// the n-th seat.
+	fn priority(&self, votes: &BigRational, n: usize) -> Priority {
+		let signpost = self.signpost.value(n);
+		if signpost.is_zero() {
+			Priority(None)
+		} else {
+			Priority(Some(votes / signpost))
+		}
+	}
+}
+
+impl AllocateSeats for DivisorSearch {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		check_votes(nb_seats, parties)?;
+		if let Signpost::Stationary(r) = self.signpost {
+			if r < Rational::from_integer(0) || r > Rational::from_integer(1) {
+				return Err(AllocationError::InvalidDivisors);
+			}
+		}
+		if nb_seats == 0 {
+			return Ok(vec![0; parties.len()]);
+		}
+		let e = self.signpost.exponent();
+		let total = parties.iter().try_fold(0usize, |sum, v| sum.checked_add(*v));
+		let total = total.ok_or(AllocationError::Overflow)?;
+		let votes: Vec<BigRational> = parties.iter().map(|v| pow(big(*v), e)).collect();
+
+		// Round the quotients for the divisor of total votes per seat. As the
+		// n-th signpost is at most n, at least the integer part of the quotient
+		// is reached, and at most one more.
+		let mut seats = Vec::with_capacity(parties.len());
+		for v in parties.iter() {
+			if *v == 0 {
+				seats.push(0);
+				continue;
+			}
+			let quotient = big(*v) * big(nb_seats) / big(total);
+			let n = quotient.to_integer().to_usize().ok_or(AllocationError::Overflow)?;
+			let reached = self.signpost.value(n + 1) <= pow(quotient, e);
+			seats.push(if reached {
+				n + 1
+			} else {
+				n
+			});
+		}
+
+		// Then move the divisor until all seats are allocated. Equal priorities
+		// favour the party listed first for now.
+		let mut allocated: usize = seats.iter().sum();
+		if allocated < nb_seats {
+			let mut heap: BinaryHeap<(Priority, Reverse<usize>)> = (0..parties.len())
+				.filter(|p| parties[*p] > 0)
+				.map(|p| (self.priority(&votes[p], seats[p] + 1), Reverse(p)))
+				.collect();
+			while allocated < nb_seats {
+				let (_, Reverse(p)) = heap.pop().unwrap();
+				seats[p] += 1;
+				allocated += 1;
+				heap.push((self.priority(&votes[p], seats[p] + 1), Reverse(p)));
+			}
+		} else if allocated > nb_seats {
+			let mut heap: BinaryHeap<(Reverse<Priority>, usize)> = (0..parties.len())
+				.filter(|p| seats[*p] > 0)
+				.map(|p| (Reverse(self.priority(&votes[p], seats[p])), p))
+				.collect();
+			while allocated > nb_seats {
+				let (_, p) = heap.pop().unwrap();
+				seats[p] -= 1;
+				allocated -= 1;
+				if seats[p] > 0 {
+					heap.push((Reverse(self.priority(&votes[p], seats[p])), p));
+				}
+			}
+		}
+
+		// The last seats are tied if the lowest priority that got a seat is also
+		// the highest priority that didn't.
+		let voted: Vec<usize> = (0..parties.len()).filter(|p| parties[*p] > 0).collect();
+		let last = voted
+			.iter()
+			.filter(|p| seats[**p] > 0)
+			.map(|p| self.priority(&votes[*p], seats[*p]))
+			.min();
+		let next = voted.iter().map(|p| self.priority(&votes[*p], seats[*p] + 1)).max();
+		if let (Some(last), Some(next)) = (last, next) {
+			if last == next {
+				let holders: Vec<usize> = voted
+					.iter()
+					.cloned()
+					.filter(|p| seats[*p] > 0 && self.priority(&votes[*p], seats[*p]) == last)
+					.collect();
+				let mut tied = holders.clone();
+				tied.extend(
+					voted.iter().filter(|p| self.priority(&votes[**p], seats[**p] + 1) == last),
+				);
+				let t = Tie::resolve(&self.tie_break, &tied, holders.len(), parties);
+				if !t.is_resolved() {
+					return Err(AllocationError::Tie(t));
+				}
+				for p in holders {
+					seats[p] -= 1;
+				}
+				for p in t.winners {
+					seats[p] += 1;
+				}
+			}
+		}
+		Ok(seats)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::{HighestAverages, Method};
+
+	#[test]
+	fn signposts() {
+		let r = |n, d| big(n) / big(d);
+		let dhondt = Signpost::Stationary(Rational::from_integer(1));
+		assert_eq!(
+			vec![r(1, 1), r(2, 1), r(3, 1)],
+			(1..4).map(|n| dhondt.value(n)).collect::<Vec<_>>()
+		);
+		let sainte_lague = Signpost::PowerMean(1);
+		assert_eq!(
+			vec![r(1, 2), r(3, 2), r(5, 2)],
+			(1..4).map(|n| sainte_lague.value(n)).collect::<Vec<_>>()
+		);
+		let huntington_hill = Signpost::PowerMean(0);
+		assert_eq!(
+			vec![r(0, 1), r(2, 1), r(6, 1)],
+			(1..4).map(|n| huntington_hill.value(n)).collect::<Vec<_>>()
+		);
+		let dean = Signpost::PowerMean(-1);
+		assert_eq!(
+			vec![r(0, 1), r(4, 3), r(12, 5)],
+			(1..4).map(|n| dean.value(n)).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn same_as_quotient_table() {
+		let cases = vec![
+			(Signpost::Stationary(Rational::from_integer(1)), Method::DHondt),
+			(Signpost::Stationary(Rational::new(1, 2)), Method::SainteLague),
+			(Signpost::Stationary(Rational::from_integer(0)), Method::Adams),
+			(Signpost::PowerMean(0), Method::HuntingtonHill),
+			(Signpost::PowerMean(-1), Method::Dean),
+		];
+		let votes = vec![
+			5030053, 736081, 7158923, 3013756, 39576757, 5782171, 3608298, 990837, 21570527,
+			10725274, 1460137, 1841377, 12822739, 6790280, 3192406, 2940865, 4509342, 4661468,
+		];
+		for (signpost, method) in cases {
+			let search = DivisorSearch::new(signpost);
+			let table = HighestAverages::new(method);
+			for nb_seats in [20, 57, 150, 301].iter() {
+				assert_eq!(
+					table.try_allocate_seats(*nb_seats, &votes),
+					search.try_allocate_seats(*nb_seats, &votes)
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn invalid_input() {
+		let search = DivisorSearch::new(Signpost::PowerMean(0));
+		assert_eq!(Err(AllocationError::NoParties), search.try_allocate_seats(5, &[]));
+		assert_eq!(Ok(vec![0, 4, 0]), search.try_allocate_seats(4, &[0, 10, 0]));
+		let stationary = |r| DivisorSearch::new(Signpost::Stationary(r));
+		let result = stationary(Rational::from_integer(3)).try_allocate_seats(5, &[10, 20]);
+		assert_eq!(Err(AllocationError::InvalidDivisors), result);
+		let result = stationary(Rational::from_integer(-1)).try_allocate_seats(5, &[10, 20]);
+		assert_eq!(Err(AllocationError::InvalidDivisors), result);
+	}
+
+	#[test]
+	fn ties() {
+		let cases = vec![
+			(Signpost::Stationary(Rational::from_integer(1)), Method::DHondt, vec![30, 20, 10]),
+			(Signpost::Stationary(Rational::new(1, 2)), Method::SainteLague, vec![10, 30, 10]),
+			(Signpost::PowerMean(0), Method::HuntingtonHill, vec![1000000, 10, 1]),
+		];
+		let tie_breaks =
+			[TieBreak::PartyIndex, TieBreak::MostVotes, TieBreak::Lot(7), TieBreak::Error];
+		for (signpost, method, votes) in cases {
+			for tie_break in tie_breaks.iter() {
+				let search = DivisorSearch::new(signpost).with_tie_break(*tie_break);
+				let table = HighestAverages::new(method).with_tie_break(*tie_break);
+				for nb_seats in 1..8 {
+					assert_eq!(
+						table.try_allocate_seats(nb_seats, &votes),
+						search.try_allocate_seats(nb_seats, &votes)
+					);
+				}
+			}
+		}
+		// With fewer seats than states, the zero signposts are all tied.
+		let search = DivisorSearch::new(Signpost::PowerMean(0)).with_tie_break(TieBreak::Error);
+		match search.try_allocate_seats(2, &[100, 10, 1]) {
+			Err(AllocationError::Tie(t)) => assert_eq!(vec![0, 1, 2], t.parties),
+			result => panic!("expected a tie, got {:?}", result),
+		}
+	}
+}
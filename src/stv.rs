@@ -0,0 +1,765 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{pow, One, Zero};
+
+use super::big;
+use ballot::Profile;
+
+/// The rules used to transfer surpluses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+	/// Only the last parcel of votes received by an elected candidate is
+	/// transferred, at the surplus divided by the value of that parcel.
+	Gregory,
+	/// All votes of an elected candidate are transferred, at the surplus
+	/// divided by the total value of the votes.
+	WeightedInclusiveGregory,
+	/// The weighted inclusive Gregory method with transfer values and vote
+	/// values truncated to 5 decimals, as in Scottish local elections.
+	Scottish,
+	/// Meek's method, where every candidate keeps a fraction of every vote
+	/// that reaches them and passes on the rest, with the keep values of the
+	/// elected candidates iterated until their votes equal the quota.
+	Meek,
+	/// Warren's method, like Meek's but every candidate takes the same amount
+	/// of every vote that reaches them instead of the same fraction.
+	Warren,
+}
+
+impl Rule {
+	/// Whether the rule iterates keep values instead of transferring papers.
+	fn is_iterative(&self) -> bool {
+		*self == Rule::Meek || *self == Rule::Warren
+	}
+}
+
+/// What happened in a round of the count.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+	/// The first preferences were counted.
+	FirstPreferences,
+	/// The surplus of an elected candidate was transferred.
+	Surplus {
+		candidate: usize,
+		transfer_value: BigRational,
+	},
+	/// A candidate was eliminated and their votes were transferred.
+	Elimination(usize),
+	/// The keep values of the elected candidates were updated to transfer
+	/// their surpluses, with Meek's or Warren's method.
+	KeepValues,
+}
+
+/// A round of the count.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Round {
+	/// The action taken in this round.
+	pub action: Action,
+	/// The number of votes needed to be elected in this round.
+	pub quota: BigRational,
+	/// The votes each candidate received in this round. With Meek's or
+	/// Warren's method, elected candidates can lose votes.
+	pub received: Vec<BigRational>,
+	/// The votes of each candidate at the end of this round.
+	pub tallies: Vec<BigRational>,
+	/// The total value of votes that could not be transferred so far.
+	pub exhausted: BigRational,
+	/// The candidates elected at the end of this round.
+	pub elected: Vec<usize>,
+	/// The keep values of all candidates in this round, only with Meek's or
+	/// Warren's method.
+	pub keep_values: Option<Vec<BigRational>>,
+}
+
+/// The result of an STV count.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Count {
+	/// The number of votes needed to be elected, in the last round.
+	pub quota: BigRational,
+	/// The elected candidates in order of election.
+	pub elected: Vec<usize>,
+	/// All rounds of the count.
+	pub rounds: Vec<Round>,
+}
+
+/// The reasons a count can fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StvError {
+	/// There are fewer candidates than seats.
+	NotEnoughCandidates,
+	/// A ballot ranks candidates equally, which is not supported.
+	EqualRanking,
+	/// There are no votes.
+	NoVotes,
+}
+
+impl fmt::Display for StvError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			StvError::NotEnoughCandidates => write!(f, "fewer candidates than seats"),
+			StvError::EqualRanking => write!(f, "candidates ranked equally on ballot"),
+			StvError::NoVotes => write!(f, "no votes"),
+		}
+	}
+}
+
+impl Error for StvError {}
+
+/// Implements the single transferable vote with the Droop quota.
+/// For more info: https://en.wikipedia.org/wiki/Counting_single_transferable_votes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Stv {
+	rule: Rule,
+	precision: u32,
+}
+
+impl Stv {
+	pub fn new(rule: Rule) -> Stv {
+		Stv {
+			rule,
+			precision: 9,
+		}
+	}
+
+	/// Set the number of decimals for the keep values of Meek's and Warren's
+	/// methods. Keep values are rounded up to this precision and iterated until
+	/// the total surplus is below it. The default is 9 decimals.
+	pub fn with_precision(self, decimals: u32) -> Stv {
+		Stv {
+			precision: decimals,
+			..self
+		}
+	}
+
+	/// Count the ballots to elect the given number of candidates. Skipped
+	/// rankings are ignored, but candidates can't be ranked equally.
+	pub fn count(&self, nb_seats: usize, profile: &Profile) -> Result<Count, StvError> {
+		let nb_candidates = profile.nb_candidates();
+		if nb_candidates < nb_seats {
+			return Err(StvError::NotEnoughCandidates);
+		}
+		let mut votes = Vec::with_capacity(profile.ballots().len());
+		for ballot in profile.ballots().iter() {
+			votes.push(Vote {
+				weight: ballot.weight,
+				preferences: ballot.strict_order().ok_or(StvError::EqualRanking)?,
+			});
+		}
+		let ballots = &votes[..];
+		let total = profile.nb_voters();
+		if total == 0 {
+			return Err(StvError::NoVotes);
+		}
+
+		if self.rule.is_iterative() {
+			let mut counter = Iterative {
+				rule: self.rule,
+				ballots,
+				nb_seats,
+				precision: BigRational::one() / pow10(self.precision),
+				total: big(total),
+				quota: BigRational::zero(),
+				states: vec![State::Continuing; nb_candidates],
+				keep_values: vec![BigRational::one(); nb_candidates],
+				tallies: vec![BigRational::zero(); nb_candidates],
+				exhausted: BigRational::zero(),
+				elected: Vec::new(),
+				rounds: Vec::new(),
+			};
+			counter.run();
+			return Ok(Count {
+				quota: counter.quota,
+				elected: counter.elected,
+				rounds: counter.rounds,
+			});
+		}
+
+		let mut counter = Counter {
+			rule: self.rule,
+			ballots,
+			quota: big(total / (nb_seats + 1) + 1),
+			states: vec![State::Continuing; nb_candidates],
+			piles: vec![Vec::new(); nb_candidates],
+			tallies: vec![BigRational::zero(); nb_candidates],
+			exhausted: BigRational::zero(),
+			elected: Vec::new(),
+			pending: Vec::new(),
+			rounds: Vec::new(),
+		};
+		counter.run(nb_seats);
+		Ok(Count {
+			quota: counter.quota,
+			elected: counter.elected,
+			rounds: counter.rounds,
+		})
+	}
+}
+
+/// A ballot as used in the count.
+struct Vote {
+	/// The number of voters that cast this ballot.
+	weight: usize,
+	/// The candidates in order of preference.
+	preferences: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+	Continuing,
+	Elected,
+	Eliminated,
+}
+
+/// A ballot paper in the pile of a candidate.
+#[derive(Clone, Debug)]
+struct Paper {
+	/// The index of the ballot.
+	ballot: usize,
+	/// The position in the preferences of the current candidate.
+	position: usize,
+	/// The current value of the paper.
+	value: BigRational,
+	/// The round in which the paper was received.
+	round: usize,
+}
+
+/// The state of a count in progress.
+struct Counter<'a> {
+	rule: Rule,
+	ballots: &'a [Vote],
+	quota: BigRational,
+	states: Vec<State>,
+	piles: Vec<Vec<Paper>>,
+	tallies: Vec<BigRational>,
+	exhausted: BigRational,
+	elected: Vec<usize>,
+	/// Elected candidates whose surplus is not transferred yet.
+	pending: Vec<usize>,
+	rounds: Vec<Round>,
+}
+
+impl<'a> Counter<'a> {
+	fn run(&mut self, nb_seats: usize) {
+		let papers = (0..self.ballots.len())
+			.map(|i| Paper {
+				ballot: i,
+				position: 0,
+				value: big(self.ballots[i].weight),
+				round: 0,
+			})
+			.collect();
+		let received = self.distribute(papers, true);
+		self.record(Action::FirstPreferences, received);
+
+		loop {
+			self.elect_over_quota();
+			if self.elected.len() >= nb_seats {
+				break;
+			}
+			let continuing = self.continuing();
+			if self.elected.len() + continuing.len() <= nb_seats {
+				// The remaining candidates fill the remaining seats.
+				let mut remaining = continuing;
+				remaining.sort_by(|a, b| self.compare(*b, *a));
+				for c in remaining {
+					self.elect(c);
+				}
+				break;
+			}
+
+			if let Some(candidate) = self.next_surplus() {
+				self.transfer_surplus(candidate);
+			} else {
+				let lowest = continuing.into_iter().min_by(|a, b| self.compare(*a, *b));
+				self.eliminate(lowest.unwrap());
+			}
+		}
+	}
+
+	fn continuing(&self) -> Vec<usize> {
+		(0..self.states.len()).filter(|c| self.states[*c] == State::Continuing).collect()
+	}
+
+	fn compare(&self, a: usize, b: usize) -> Ordering {
+		compare(&self.tallies, &self.rounds, a, b)
+	}
+
+	/// Give the papers to the next continuing candidate on their ballots and
+	/// return the votes received by each candidate.
+	fn distribute(&mut self, papers: Vec<Paper>, first: bool) -> Vec<BigRational> {
+		let round = self.rounds.len();
+		let mut received = vec![BigRational::zero(); self.states.len()];
+		for mut paper in papers.into_iter() {
+			let preferences = &self.ballots[paper.ballot].preferences;
+			let start = if first {
+				0
+			} else {
+				paper.position + 1
+			};
+			let next = (start..preferences.len())
+				.find(|p| self.states[preferences[*p]] == State::Continuing);
+			match next {
+				Some(position) => {
+					let candidate = preferences[position];
+					paper.position = position;
+					paper.round = round;
+					received[candidate] += &paper.value;
+					self.tallies[candidate] += &paper.value;
+					self.piles[candidate].push(paper);
+				}
+				None => self.exhausted += paper.value,
+			}
+		}
+		received
+	}
+
+	fn record(&mut self, action: Action, received: Vec<BigRational>) {
+		self.rounds.push(Round {
+			action,
+			quota: self.quota.clone(),
+			received,
+			tallies: self.tallies.clone(),
+			exhausted: self.exhausted.clone(),
+			elected: Vec::new(),
+			keep_values: None,
+		});
+	}
+
+	fn elect(&mut self, candidate: usize) {
+		self.states[candidate] = State::Elected;
+		self.elected.push(candidate);
+		self.pending.push(candidate);
+		self.rounds.last_mut().unwrap().elected.push(candidate);
+	}
+
+	/// Elect all continuing candidates that reached the quota, highest first.
+	fn elect_over_quota(&mut self) {
+		let mut over: Vec<usize> =
+			self.continuing().into_iter().filter(|c| self.tallies[*c] >= self.quota).collect();
+		over.sort_by(|a, b| self.compare(*b, *a));
+		for c in over {
+			self.elect(c);
+		}
+	}
+
+	/// Take the largest surplus that is not transferred yet.
+	fn next_surplus(&mut self) -> Option<usize> {
+		let quota = &self.quota;
+		let tallies = &self.tallies;
+		self.pending.retain(|c| tallies[*c] > *quota);
+		let largest = self.pending.iter().cloned().max_by(|a, b| self.compare(*a, *b));
+		if let Some(c) = largest {
+			self.pending.retain(|p| *p != c);
+		}
+		largest
+	}
+
+	fn transfer_surplus(&mut self, candidate: usize) {
+		let surplus = &self.tallies[candidate] - &self.quota;
+		let pile = ::std::mem::take(&mut self.piles[candidate]);
+		let papers: Vec<Paper> = match self.rule {
+			Rule::Gregory => {
+				let last = pile.iter().map(|p| p.round).max().unwrap_or(0);
+				pile.into_iter().filter(|p| p.round == last).collect()
+			}
+			_ => pile,
+		};
+		let value = papers.iter().fold(BigRational::zero(), |sum, p| sum + &p.value);
+		let mut transfer_value = surplus / value;
+		if transfer_value > BigRational::one() {
+			transfer_value = BigRational::one();
+		}
+		if self.rule == Rule::Scottish {
+			transfer_value = truncate(&transfer_value);
+		}
+
+		let mut transferred = Vec::with_capacity(papers.len());
+		for mut paper in papers.into_iter() {
+			paper.value = &paper.value * &transfer_value;
+			if self.rule == Rule::Scottish {
+				paper.value = truncate(&paper.value);
+			}
+			transferred.push(paper);
+		}
+		self.tallies[candidate] = self.quota.clone();
+		let received = self.distribute(transferred, false);
+		self.record(
+			Action::Surplus {
+				candidate,
+				transfer_value,
+			},
+			received,
+		);
+	}
+
+	fn eliminate(&mut self, candidate: usize) {
+		self.states[candidate] = State::Eliminated;
+		let pile = ::std::mem::take(&mut self.piles[candidate]);
+		self.tallies[candidate] = BigRational::zero();
+		let received = self.distribute(pile, false);
+		self.record(Action::Elimination(candidate), received);
+	}
+}
+
+/// The state of a count with Meek's or Warren's method in progress.
+struct Iterative<'a> {
+	rule: Rule,
+	ballots: &'a [Vote],
+	nb_seats: usize,
+	precision: BigRational,
+	total: BigRational,
+	quota: BigRational,
+	states: Vec<State>,
+	keep_values: Vec<BigRational>,
+	tallies: Vec<BigRational>,
+	exhausted: BigRational,
+	elected: Vec<usize>,
+	rounds: Vec<Round>,
+}
+
+impl<'a> Iterative<'a> {
+	/// The maximum number of iterations to find the keep values in a round.
+	const MAX_ITERATIONS: usize = 1000;
+
+	fn run(&mut self) {
+		let mut action = Action::FirstPreferences;
+		loop {
+			self.converge();
+			self.record(action);
+
+			// Elect all hopeful candidates over the quota, highest first.
+			let hopeful = self.hopeful();
+			let mut over: Vec<usize> =
+				hopeful.iter().cloned().filter(|c| self.tallies[*c] > self.quota).collect();
+			over.sort_by(|a, b| compare(&self.tallies, &self.rounds, *b, *a));
+			for c in over.iter() {
+				self.elect(*c);
+			}
+			if self.elected.len() >= self.nb_seats {
+				break;
+			}
+
+			let hopeful = self.hopeful();
+			if self.elected.len() + hopeful.len() <= self.nb_seats {
+				// The remaining candidates fill the remaining seats.
+				let mut remaining = hopeful;
+				remaining.sort_by(|a, b| compare(&self.tallies, &self.rounds, *b, *a));
+				for c in remaining {
+					self.elect(c);
+				}
+				break;
+			}
+
+			if !over.is_empty() {
+				action = Action::KeepValues;
+			} else {
+				let lowest = hopeful
+					.into_iter()
+					.min_by(|a, b| compare(&self.tallies, &self.rounds, *a, *b))
+					.unwrap();
+				self.states[lowest] = State::Eliminated;
+				self.keep_values[lowest] = BigRational::zero();
+				action = Action::Elimination(lowest);
+			}
+		}
+	}
+
+	fn hopeful(&self) -> Vec<usize> {
+		(0..self.states.len()).filter(|c| self.states[*c] == State::Continuing).collect()
+	}
+
+	fn elect(&mut self, candidate: usize) {
+		self.states[candidate] = State::Elected;
+		self.elected.push(candidate);
+		self.rounds.last_mut().unwrap().elected.push(candidate);
+	}
+
+	/// Update the keep values of the elected candidates until their votes are
+	/// equal to the quota, within the precision.
+	fn converge(&mut self) {
+		for _ in 0..Self::MAX_ITERATIONS {
+			self.distribute();
+			let surplus = self
+				.elected
+				.iter()
+				.fold(BigRational::zero(), |sum, c| sum + (&self.tallies[*c] - &self.quota));
+			if surplus <= self.precision {
+				break;
+			}
+
+			let mut changed = false;
+			for c in self.elected.iter() {
+				let keep = &self.keep_values[*c] * &self.quota / &self.tallies[*c];
+				let keep = (keep / &self.precision).ceil() * &self.precision;
+				if keep != self.keep_values[*c] {
+					self.keep_values[*c] = keep;
+					changed = true;
+				}
+			}
+			if !changed {
+				break;
+			}
+		}
+	}
+
+	/// Distribute all votes according to the keep values and calculate the
+	/// quota.
+	fn distribute(&mut self) {
+		for tally in self.tallies.iter_mut() {
+			*tally = BigRational::zero();
+		}
+		self.exhausted = BigRational::zero();
+		let mut seen = vec![false; self.states.len()];
+		for ballot in self.ballots.iter() {
+			let weight = big(ballot.weight);
+			// The part of a single vote that is not kept yet.
+			let mut remaining = BigRational::one();
+			for s in seen.iter_mut() {
+				*s = false;
+			}
+			for c in ballot.preferences.iter() {
+				if seen[*c] || self.states[*c] == State::Eliminated {
+					continue;
+				}
+				seen[*c] = true;
+				let keep = match self.rule {
+					Rule::Warren => {
+						if self.keep_values[*c] < remaining {
+							self.keep_values[*c].clone()
+						} else {
+							remaining.clone()
+						}
+					}
+					_ => &remaining * &self.keep_values[*c],
+				};
+				remaining -= &keep;
+				self.tallies[*c] += keep * &weight;
+				if remaining.is_zero() {
+					break;
+				}
+			}
+			self.exhausted += remaining * weight;
+		}
+		self.quota = (&self.total - &self.exhausted) / big(self.nb_seats + 1);
+	}
+
+	fn record(&mut self, action: Action) {
+		let received = match self.rounds.last() {
+			Some(last) => {
+				self.tallies.iter().zip(last.tallies.iter()).map(|(t, l)| t - l).collect()
+			}
+			None => self.tallies.clone(),
+		};
+		self.rounds.push(Round {
+			action,
+			quota: self.quota.clone(),
+			received,
+			tallies: self.tallies.clone(),
+			exhausted: self.exhausted.clone(),
+			elected: Vec::new(),
+			keep_values: Some(self.keep_values.clone()),
+		});
+	}
+}
+
+/// Compare the votes of two candidates. Equal votes are compared by the votes
+/// in the most recent round in which they differed, and then by the order of
+/// the candidates, where the candidate listed first is higher.
+fn compare(tallies: &[BigRational], rounds: &[Round], a: usize, b: usize) -> Ordering {
+	let history = rounds.iter().rev().map(|r| &r.tallies[..]);
+	for tally in Some(tallies).into_iter().chain(history) {
+		match tally[a].cmp(&tally[b]) {
+			Ordering::Equal => continue,
+			ord => return ord,
+		}
+	}
+	b.cmp(&a)
+}
+
+/// Calculate 10 to the given power.
+fn pow10(exp: u32) -> BigRational {
+	BigRational::from_integer(pow(BigInt::from(10), exp as usize))
+}
+
+/// Truncate a value to 5 decimals.
+fn truncate(value: &BigRational) -> BigRational {
+	let scale = big(100000);
+	(value * &scale).floor() / scale
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ballot::fixtures::profile;
+	use ballot::Ballot;
+
+	fn ratio(n: usize, d: usize) -> BigRational {
+		big(n) / big(d)
+	}
+
+	fn food_ballots() -> Profile {
+		let names = vec!["Oranges", "Pears", "Chocolate", "Strawberries", "Burgers"];
+		let candidates = names.into_iter().map(|n| n.to_owned()).collect();
+		let ballots = vec![
+			Ballot::new(4, vec![0]),
+			Ballot::new(2, vec![1, 0]),
+			Ballot::new(8, vec![2, 3]),
+			Ballot::new(4, vec![2, 4]),
+			Ballot::new(1, vec![3]),
+			Ballot::new(1, vec![4]),
+		];
+		Profile::with_ballots(candidates, ballots).unwrap()
+	}
+
+	#[test]
+	fn example_wikipedia() {
+		for rule in [Rule::Gregory, Rule::WeightedInclusiveGregory, Rule::Scottish].iter() {
+			let count = Stv::new(*rule).count(3, &food_ballots()).unwrap();
+			assert_eq!(big(6), count.quota);
+			assert_eq!(vec![2, 0, 3], count.elected);
+			assert_eq!(
+				vec![
+					Action::FirstPreferences,
+					Action::Surplus {
+						candidate: 2,
+						transfer_value: ratio(1, 2),
+					},
+					Action::Elimination(1),
+					Action::Elimination(4),
+				],
+				count.rounds.iter().map(|r| r.action.clone()).collect::<Vec<_>>()
+			);
+			assert_eq!(vec![big(6), big(0), big(6), big(5), big(3)], count.rounds[2].tallies);
+			assert_eq!(big(3), count.rounds[3].exhausted);
+		}
+	}
+
+	#[test]
+	fn last_parcel() {
+		// Candidate 0 reaches the quota of 5 after the elimination of
+		// candidate 3, only that parcel is transferred with Gregory.
+		let ballots = vec![
+			Ballot::new(4, vec![0, 1]),
+			Ballot::new(2, vec![3, 0, 2]),
+			Ballot::new(3, vec![1]),
+			Ballot::new(3, vec![2]),
+		];
+		let gregory = Stv::new(Rule::Gregory).count(2, &profile(4, ballots.clone())).unwrap();
+		assert_eq!(big(5), gregory.quota);
+		assert_eq!(
+			Action::Surplus {
+				candidate: 0,
+				transfer_value: ratio(1, 2),
+			},
+			gregory.rounds[2].action
+		);
+		assert_eq!(big(4), gregory.rounds[2].tallies[2]);
+
+		let wigm = Stv::new(Rule::WeightedInclusiveGregory).count(2, &profile(4, ballots)).unwrap();
+		assert_eq!(
+			Action::Surplus {
+				candidate: 0,
+				transfer_value: ratio(1, 6),
+			},
+			wigm.rounds[2].action
+		);
+		assert_eq!(ratio(11, 3), wigm.rounds[2].tallies[1]);
+		assert_eq!(ratio(10, 3), wigm.rounds[2].tallies[2]);
+	}
+
+	#[test]
+	fn scottish_truncation() {
+		let ballots =
+			vec![Ballot::new(3, vec![0, 1]), Ballot::new(1, vec![1]), Ballot::new(1, vec![2])];
+		// The quota is 2, so a third of the votes of candidate 0 transfer.
+		let count = Stv::new(Rule::Scottish).count(2, &profile(3, ballots)).unwrap();
+		assert_eq!(
+			Action::Surplus {
+				candidate: 0,
+				transfer_value: ratio(33333, 100000),
+			},
+			count.rounds[1].action
+		);
+		assert_eq!(ratio(99999, 100000), count.rounds[1].received[1]);
+		assert_eq!(vec![0, 1], count.elected);
+	}
+
+	#[test]
+	fn meek_and_warren() {
+		let ballots = vec![
+			Ballot::new(7, vec![0, 1]),
+			Ballot::new(2, vec![1]),
+			Ballot::new(3, vec![2]),
+			Ballot::new(3, vec![3]),
+		];
+		for rule in [Rule::Meek, Rule::Warren].iter() {
+			let count = Stv::new(*rule).count(2, &profile(4, ballots.clone())).unwrap();
+			assert_eq!(vec![0, 1], count.elected);
+			let actions: Vec<Action> = count.rounds.iter().map(|r| r.action.clone()).collect();
+			assert_eq!(
+				vec![Action::FirstPreferences, Action::KeepValues, Action::Elimination(3)],
+				actions
+			);
+			assert_eq!(big(5), count.rounds[0].quota);
+			assert_eq!(Some(vec![big(1); 4]), count.rounds[0].keep_values);
+			// Candidate 0 keeps 5 of its 7 votes, rounded up to 9 decimals.
+			let keep = &count.rounds[1].keep_values.as_ref().unwrap()[0];
+			assert_eq!(&ratio(714285715, 1000000000), keep);
+			let surplus = &count.rounds[1].tallies[0] - big(5);
+			assert!(surplus > BigRational::zero() && surplus <= ratio(7, 1000000000));
+			// The votes for candidate 3 exhaust and the quota drops to 4.
+			assert_eq!(big(4), count.rounds[2].quota);
+			assert!(count.rounds[2].tallies[1] > count.rounds[2].quota);
+			for round in count.rounds.iter() {
+				let sum = round.tallies.iter().fold(round.exhausted.clone(), |s, t| s + t);
+				assert_eq!(big(15), sum);
+			}
+		}
+	}
+
+	#[test]
+	fn meek_transfers_through_elected() {
+		// The surpluses of candidates 0 and 1 pass through each other. With
+		// Meek, each keeps a fraction of what reaches them, with Warren, each
+		// takes the same amount from every vote.
+		let ballots = vec![
+			Ballot::new(9, vec![0, 1, 2]),
+			Ballot::new(6, vec![1, 0, 3]),
+			Ballot::new(2, vec![2]),
+			Ballot::new(3, vec![3]),
+		];
+		let meek =
+			Stv::new(Rule::Meek).with_precision(6).count(3, &profile(4, ballots.clone())).unwrap();
+		let warren =
+			Stv::new(Rule::Warren).with_precision(6).count(3, &profile(4, ballots)).unwrap();
+		for count in [&meek, &warren].iter() {
+			assert_eq!(vec![0, 1, 3], count.elected);
+			let round = &count.rounds[1];
+			assert_eq!(Action::KeepValues, round.action);
+			let keep = round.keep_values.as_ref().unwrap();
+			assert!(keep[0] < BigRational::one() && keep[1] < BigRational::one());
+			for c in 0..2 {
+				let surplus = &round.tallies[c] - &round.quota;
+				assert!(surplus >= BigRational::zero() && surplus <= ratio(15, 1000000));
+			}
+			assert!(round.tallies[2] > big(2) && round.tallies[3] > big(3));
+		}
+		assert_ne!(meek.rounds[1].keep_values, warren.rounds[1].keep_values);
+	}
+
+	#[test]
+	fn invalid_input() {
+		let stv = Stv::new(Rule::WeightedInclusiveGregory);
+		assert_eq!(Err(StvError::NotEnoughCandidates), stv.count(3, &profile(2, vec![])));
+		let equal = Ballot::with_ranks(1, vec![vec![0, 1]]);
+		assert_eq!(Err(StvError::EqualRanking), stv.count(1, &profile(2, vec![equal])));
+		assert_eq!(
+			Err(StvError::NoVotes),
+			stv.count(1, &profile(2, vec![Ballot::new(0, vec![1])]))
+		);
+	}
+}
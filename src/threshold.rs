@@ -0,0 +1,180 @@
+use num_bigint::BigInt;
+use num_rational::Rational;
+
+use super::{AllocateSeats, AllocationError};
+
+/// A condition a party can meet to qualify for seats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Condition {
+	/// A minimum share of the total number of votes, e.g. 5/100.
+	VoteShare(Rational),
+	/// A minimum number of votes.
+	Votes(usize),
+	/// A minimum number of district seats won, e.g. the German
+	/// Grundmandatsklausel of 3 constituencies.
+	DistrictSeats(usize),
+}
+
+impl Condition {
+	/// Whether the party with the given votes and district seats meets the
+	/// condition.
+	fn is_met(&self, votes: usize, total_votes: usize, district_seats: usize) -> bool {
+		match *self {
+			Condition::VoteShare(share) => {
+				BigInt::from(votes) * BigInt::from(*share.denom())
+					>= BigInt::from(*share.numer()) * BigInt::from(total_votes)
+			}
+			Condition::Votes(min) => votes >= min,
+			Condition::DistrictSeats(min) => district_seats >= min,
+		}
+	}
+}
+
+/// A party that was excluded from the seat allocation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Exclusion {
+	/// The index of the party.
+	pub party: usize,
+	/// The conditions the party did not meet.
+	pub failed: Vec<Condition>,
+}
+
+/// The outcome of a seat allocation with a threshold.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ThresholdAllocation {
+	/// The number of seats per party.
+	pub seats: Vec<usize>,
+	/// The parties that did not qualify for seats.
+	pub excluded: Vec<Exclusion>,
+}
+
+/// Applies an electoral threshold before allocating seats with another method.
+/// A party qualifies if it meets any of the conditions; parties that don't
+/// qualify get no seats and their votes are not considered.
+/// For more info: https://en.wikipedia.org/wiki/Electoral_threshold
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Threshold<A> {
+	allocator: A,
+	conditions: Vec<Condition>,
+}
+
+impl<A: AllocateSeats> Threshold<A> {
+	pub fn new(allocator: A, condition: Condition) -> Threshold<A> {
+		Threshold {
+			allocator,
+			conditions: vec![condition],
+		}
+	}
+
+	/// Add an alternative condition to qualify for seats.
+	pub fn or(mut self, condition: Condition) -> Threshold<A> {
+		self.conditions.push(condition);
+		self
+	}
+
+	/// Find the parties that don't meet any of the conditions. The district
+	/// seats per party may be empty if no party won any.
+	pub fn excluded(
+		&self,
+		parties: &[usize],
+		district_seats: &[usize],
+	) -> Result<Vec<Exclusion>, AllocationError> {
+		let total_votes = parties.iter().try_fold(0usize, |sum, v| sum.checked_add(*v));
+		let total_votes = total_votes.ok_or(AllocationError::Overflow)?;
+		let mut excluded = Vec::new();
+		for (idx, votes) in parties.iter().enumerate() {
+			let seats = district_seats.get(idx).cloned().unwrap_or(0);
+			if self.conditions.iter().any(|c| c.is_met(*votes, total_votes, seats)) {
+				continue;
+			}
+			excluded.push(Exclusion {
+				party: idx,
+				failed: self.conditions.clone(),
+			});
+		}
+		Ok(excluded)
+	}
+
+	/// Calculates the seats per party among the parties that qualify, and
+	/// reports the parties that were excluded. If no party qualifies, this
+	/// fails with [AllocationError::NoParties].
+	pub fn allocate(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+		district_seats: &[usize],
+	) -> Result<ThresholdAllocation, AllocationError> {
+		let excluded = self.excluded(parties, district_seats)?;
+		let qualified: Vec<usize> =
+			(0..parties.len()).filter(|p| !excluded.iter().any(|e| e.party == *p)).collect();
+
+		let votes: Vec<usize> = qualified.iter().map(|p| parties[*p]).collect();
+		let allocated = self.allocator.try_allocate_seats(nb_seats, &votes)?;
+		let mut seats = vec![0; parties.len()];
+		for (party, nb) in qualified.iter().zip(allocated) {
+			seats[*party] = nb;
+		}
+		Ok(ThresholdAllocation {
+			seats,
+			excluded,
+		})
+	}
+}
+
+impl<A: AllocateSeats> AllocateSeats for Threshold<A> {
+	fn try_allocate_seats(
+		&self,
+		nb_seats: usize,
+		parties: &[usize],
+	) -> Result<Vec<usize>, AllocationError> {
+		self.allocate(nb_seats, parties, &[]).map(|a| a.seats)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use highest_averages::{HighestAverages, Method};
+
+	#[test]
+	fn vote_share() {
+		let threshold = Threshold::new(
+			HighestAverages::new(Method::DHondt),
+			Condition::VoteShare(Rational::new(5, 100)),
+		);
+		let allocation = threshold.allocate(10, &[500, 300, 160, 40], &[]).unwrap();
+		assert_eq!(vec![6, 3, 1, 0], allocation.seats);
+		assert_eq!(1, allocation.excluded.len());
+		assert_eq!(3, allocation.excluded[0].party);
+		assert_eq!(vec![0, 0, 0], threshold.allocate_seats(0, vec![10, 10, 10]));
+		let huge = usize::MAX;
+		assert_eq!(Err(AllocationError::Overflow), threshold.try_allocate_seats(5, &[huge, 1]));
+		let huge = isize::MAX as usize;
+		assert_eq!(Ok(vec![5, 0]), threshold.try_allocate_seats(5, &[huge, 1]));
+	}
+
+	#[test]
+	fn alternative_conditions() {
+		let threshold = Threshold::new(
+			HighestAverages::new(Method::SainteLague),
+			Condition::VoteShare(Rational::new(5, 100)),
+		)
+		.or(Condition::DistrictSeats(3));
+		let parties = [600, 320, 45, 35];
+		let allocation = threshold.allocate(20, &parties, &[10, 5, 3, 0]).unwrap();
+		assert_eq!(vec![12, 7, 1, 0], allocation.seats);
+		assert_eq!(
+			vec![Exclusion {
+				party: 3,
+				failed: vec![
+					Condition::VoteShare(Rational::new(5, 100)),
+					Condition::DistrictSeats(3)
+				],
+			}],
+			allocation.excluded
+		);
+
+		let threshold = Threshold::new(HighestAverages::new(Method::DHondt), Condition::Votes(100));
+		assert_eq!(Err(AllocationError::NoParties), threshold.try_allocate_seats(5, &[10, 20]));
+	}
+}
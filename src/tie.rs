@@ -0,0 +1,173 @@
+/// The policy used to decide which of a number of tied parties gets a seat.
+// Callbacks are compared by address, which is good enough to compare policies.
+#[allow(unknown_lints, unpredictable_function_pointer_comparisons)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TieBreak {
+	/// The party with the most votes wins, equal votes favour the party
+	/// listed first.
+	MostVotes,
+	/// The party listed first wins.
+	#[default]
+	PartyIndex,
+	/// Draw lots using a pseudo-random generator with the given seed.
+	Lot(u64),
+	/// A callback that is given the tied parties and the votes of all parties
+	/// and returns the tied parties in order of precedence. If it doesn't
+	/// return a permutation of the tied parties, the tie is not broken.
+	Callback(fn(&[usize], &[usize]) -> Vec<usize>),
+	/// Don't break the tie but report it as an error.
+	Error,
+}
+
+impl TieBreak {
+	/// Order the tied parties by precedence, given the votes of all parties.
+	/// Returns None if the policy does not break ties, or the callback did not
+	/// return a permutation of the tied parties.
+	pub fn order(&self, tied: &[usize], votes: &[usize]) -> Option<Vec<usize>> {
+		let mut order = tied.to_vec();
+		order.sort();
+		match *self {
+			TieBreak::MostVotes => order.sort_by_key(|p| ::std::cmp::Reverse(votes[*p])),
+			TieBreak::PartyIndex => {}
+			TieBreak::Lot(seed) => Lot::new(seed).shuffle(&mut order),
+			TieBreak::Callback(callback) => {
+				let result = callback(&order, votes);
+				let mut check = result.clone();
+				check.sort();
+				if order != check {
+					return None;
+				}
+				order = result;
+			}
+			TieBreak::Error => return None,
+		}
+		Some(order)
+	}
+}
+
+/// A tie between parties competing for the last seats.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tie {
+	/// The tied parties, sorted by index.
+	pub parties: Vec<usize>,
+	/// The number of seats the tied parties compete for.
+	pub seats: usize,
+	/// The parties that were given the seats, empty if the tie was not broken.
+	pub winners: Vec<usize>,
+}
+
+impl Tie {
+	/// Break a tie between the given parties for the given number of seats.
+	/// If the policy does not break ties, the resulting tie has no winners.
+	pub fn resolve(tie_break: &TieBreak, tied: &[usize], seats: usize, votes: &[usize]) -> Tie {
+		let mut parties = tied.to_vec();
+		parties.sort();
+		let winners = match tie_break.order(&parties, votes) {
+			Some(order) => order.into_iter().take(seats).collect(),
+			None => Vec::new(),
+		};
+		Tie {
+			parties,
+			seats,
+			winners,
+		}
+	}
+
+	/// Whether the tie was broken.
+	pub fn is_resolved(&self) -> bool {
+		!self.winners.is_empty() || self.seats == 0
+	}
+}
+
+/// A small deterministic pseudo-random generator used to draw lots.
+/// This is the SplitMix64 generator, which is reproducible across platforms.
+pub(crate) struct Lot {
+	state: u64,
+}
+
+impl Lot {
+	pub fn new(seed: u64) -> Lot {
+		Lot {
+			state: seed,
+		}
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	/// Pick a number in the range [0, n) uniformly, by rejecting the lowest
+	/// 2^64 mod n values so that every remainder is equally likely.
+	pub fn below(&mut self, n: usize) -> usize {
+		let n = n as u64;
+		let threshold = n.wrapping_neg() % n;
+		loop {
+			let x = self.next_u64();
+			if x >= threshold {
+				return (x % n) as usize;
+			}
+		}
+	}
+
+	/// Shuffle the slice using the Fisher-Yates algorithm.
+	pub fn shuffle<T>(&mut self, items: &mut [T]) {
+		for i in (1..items.len()).rev() {
+			let j = self.below(i + 1);
+			items.swap(i, j);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn reversed(tied: &[usize], _votes: &[usize]) -> Vec<usize> {
+		tied.iter().rev().cloned().collect()
+	}
+
+	fn truncated(tied: &[usize], _votes: &[usize]) -> Vec<usize> {
+		tied[1..].to_vec()
+	}
+
+	#[test]
+	fn order() {
+		let votes = vec![10, 30, 20, 30];
+		let tied = vec![3, 0, 1];
+		assert_eq!(Some(vec![1, 3, 0]), TieBreak::MostVotes.order(&tied, &votes));
+		assert_eq!(Some(vec![0, 1, 3]), TieBreak::PartyIndex.order(&tied, &votes));
+		assert_eq!(Some(vec![3, 1, 0]), TieBreak::Callback(reversed).order(&tied, &votes));
+		assert_eq!(None, TieBreak::Error.order(&tied, &votes));
+		assert_eq!(None, TieBreak::Callback(truncated).order(&tied, &votes));
+		let tie = Tie::resolve(&TieBreak::Callback(truncated), &tied, 1, &votes);
+		assert_eq!(vec![0, 1, 3], tie.parties);
+		assert!(!tie.is_resolved());
+	}
+
+	#[test]
+	fn lot_is_reproducible() {
+		let votes = vec![1; 10];
+		let tied: Vec<usize> = (0..10).collect();
+		let first = TieBreak::Lot(42).order(&tied, &votes).unwrap();
+		assert_eq!(first, TieBreak::Lot(42).order(&tied, &votes).unwrap());
+		assert_ne!(tied, first);
+		let mut sorted = first.clone();
+		sorted.sort();
+		assert_eq!(tied, sorted);
+	}
+
+	#[test]
+	#[cfg(target_pointer_width = "64")]
+	fn uniform_lot() {
+		// Taking the remainder would make the lower half twice as likely as
+		// the upper half for this range.
+		let n = (u64::MAX / 3 * 2) as usize;
+		let mut lot = Lot::new(42);
+		let low = (0..3000).filter(|_| lot.below(n) < n / 2).count();
+		assert!(low > 1350 && low < 1650, "{}", low);
+	}
+}